@@ -0,0 +1,254 @@
+use std::{fmt, path::Path, time::Duration};
+
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::time::{sleep, Instant};
+
+use super::{post_form, OAuthConfig, OAuthRequestError, TokenResponse, AUTHORIZE_PATH, TOKEN_PATH};
+use crate::profile::{Auth, OAuth, ProfileFile, ProfileFileLoadError, ProfileFileSaveError};
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+/// RFC 8628 section 3.5: back off by 5 seconds on `slow_down`.
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+const DEFAULT_INTERVAL: u64 = 5;
+
+/// The response of the device authorization endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Lifetime of the device and user codes in seconds.
+    pub expires_in: u64,
+    /// Minimum number of seconds between token requests.
+    pub interval: Option<u64>,
+}
+
+impl fmt::Display for DeviceAuthorization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "To verify your account, copy your one-time verification code: {}",
+            self.user_code
+        )?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Paste the code in the browser when prompted to activate your Atlas CLI. Your code will expire after {} minutes.",
+            self.expires_in / 60
+        )?;
+        writeln!(f)?;
+        write!(f, "To continue, go to {}", self.verification_uri)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DeviceFlowError {
+    #[error("request to the authorization server failed")]
+    Request(#[from] OAuthRequestError),
+    #[error("the verification code expired before the login was approved")]
+    Expired,
+    #[error("the login was denied")]
+    AccessDenied,
+}
+
+/// The OAuth 2.0 device authorization grant (RFC 8628) against the Atlas authorization
+/// server.
+#[derive(Clone, Debug)]
+pub struct DeviceFlow {
+    config: OAuthConfig,
+    http: reqwest::Client,
+}
+
+impl DeviceFlow {
+    pub fn new(config: OAuthConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn config(&self) -> &OAuthConfig {
+        &self.config
+    }
+
+    /// Requests a device and user code.
+    pub async fn request_code(&self) -> Result<DeviceAuthorization, DeviceFlowError> {
+        let scope = self.config.scope();
+        let form = [
+            ("client_id", self.config.client_id.as_str()),
+            ("scope", scope.as_str()),
+        ];
+        Ok(post_form(&self.http, self.config.endpoint(AUTHORIZE_PATH), &form).await?)
+    }
+
+    /// Polls the token endpoint until the user approved or denied the login, or the code
+    /// expired.
+    pub async fn poll_token(
+        &self,
+        authorization: &DeviceAuthorization,
+    ) -> Result<TokenResponse, DeviceFlowError> {
+        let deadline = Instant::now() + Duration::from_secs(authorization.expires_in);
+        let mut interval = Duration::from_secs(authorization.interval.unwrap_or(DEFAULT_INTERVAL));
+
+        let form = [
+            ("grant_type", DEVICE_CODE_GRANT),
+            ("client_id", self.config.client_id.as_str()),
+            ("device_code", authorization.device_code.as_str()),
+        ];
+
+        loop {
+            if Instant::now() + interval > deadline {
+                return Err(DeviceFlowError::Expired);
+            }
+            sleep(interval).await;
+
+            let (error, description) =
+                match post_form(&self.http, self.config.endpoint(TOKEN_PATH), &form).await {
+                    Ok(tokens) => return Ok(tokens),
+                    Err(OAuthRequestError::OAuth { error, description }) => (error, description),
+                    Err(e) => return Err(e.into()),
+                };
+
+            match error.as_str() {
+                "authorization_pending" => {}
+                "slow_down" => interval += SLOW_DOWN_INCREMENT,
+                "expired_token" => return Err(DeviceFlowError::Expired),
+                "access_denied" => return Err(DeviceFlowError::AccessDenied),
+                _ => return Err(OAuthRequestError::OAuth { error, description }.into()),
+            }
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LoginError {
+    #[error("failed to load the profile file")]
+    Load(#[from] ProfileFileLoadError),
+    #[error("device authorization failed")]
+    DeviceFlow(#[from] DeviceFlowError),
+    #[error("failed to lock the profile file")]
+    Lock(#[source] std::io::Error),
+    #[error("the authorization server didn't issue a refresh token")]
+    MissingRefreshToken,
+    #[error("failed to save the profile file")]
+    Save(#[from] ProfileFileSaveError),
+}
+
+/// Logs the profile `profile_name` in using the device authorization grant and stores the
+/// resulting tokens in the profile file at `path`.
+///
+/// `prompt` is called once the user code is known, and is expected to show it together
+/// with the verification URI (the [`Display`](fmt::Display) implementation of
+/// [`DeviceAuthorization`] does exactly that).
+pub async fn login(
+    path: impl AsRef<Path>,
+    profile_name: &str,
+    prompt: impl FnOnce(&DeviceAuthorization),
+) -> Result<OAuth, LoginError> {
+    let path = path.as_ref();
+    let profile_file = ProfileFile::load_or_default(path).await?;
+    let profile = profile_file
+        .profile(profile_name)
+        .cloned()
+        .unwrap_or_default();
+    let flow = DeviceFlow::new(OAuthConfig::for_profile(&profile));
+
+    let authorization = flow.request_code().await?;
+    prompt(&authorization);
+    let response = flow.poll_token(&authorization).await?;
+    let Some(refresh_token) = response.refresh_token.clone() else {
+        return Err(LoginError::MissingRefreshToken);
+    };
+    let oauth = response.into_oauth(&refresh_token);
+
+    // Reload in case the file changed while the user was logging in, e.g. by a refresh of
+    // another profile, which can't save in between while the lock is held.
+    let _lock = ProfileFile::lock(path).await.map_err(LoginError::Lock)?;
+    let mut profile_file = ProfileFile::load_or_default(path).await?;
+    profile_file.profile_mut(profile_name).auth = Some(Auth::OAuth(oauth.clone()));
+    profile_file.save(path).await?;
+
+    Ok(oauth)
+}
+
+#[cfg(test)]
+mod tests {
+    use atlas_mock::MockServer;
+
+    use super::*;
+    use crate::profile::Profile;
+
+    async fn profile_file(server: &MockServer) -> (tempfile::TempDir, std::path::PathBuf) {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("config.toml");
+
+        let mut profile_file = ProfileFile::default();
+        profile_file.profiles.insert(
+            "dev".to_string(),
+            Profile {
+                base_url: Some(server.base_url().clone()),
+                org_id: Some("org".to_string()),
+                ..Default::default()
+            },
+        );
+        profile_file.save(&path).await.unwrap();
+
+        (directory, path)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn login_stores_tokens() {
+        let server = MockServer::start().await.unwrap();
+        let (_directory, path) = profile_file(&server).await;
+
+        server.queue_token_error("authorization_pending");
+        server.queue_token_error("slow_down");
+
+        let started = Instant::now();
+        let oauth = login(&path, "dev", |authorization| {
+            assert!(authorization.to_string().contains(&authorization.user_code));
+            assert!(server.approve_device(&authorization.user_code));
+        })
+        .await
+        .unwrap();
+
+        // pending after 5s, slow down after 10s, then the interval is 10s
+        assert_eq!(started.elapsed().as_secs(), 20);
+
+        let profile_file = ProfileFile::load(&path).await.unwrap();
+        let profile = profile_file.profile("dev").unwrap();
+        assert_eq!(profile.org_id.as_deref(), Some("org"));
+        assert!(matches!(&profile.auth, Some(Auth::OAuth(stored)) if *stored == oauth));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn login_denied() {
+        let server = MockServer::start().await.unwrap();
+        let (_directory, path) = profile_file(&server).await;
+
+        let result = login(&path, "dev", |authorization| {
+            server.deny_device(&authorization.user_code);
+        })
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(LoginError::DeviceFlow(DeviceFlowError::AccessDenied))
+        ));
+        let profile_file = ProfileFile::load(&path).await.unwrap();
+        assert!(profile_file.profile("dev").unwrap().auth.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn login_expires() {
+        let server = MockServer::start().await.unwrap();
+        let (_directory, path) = profile_file(&server).await;
+
+        let result = login(&path, "dev", |_| {}).await;
+        assert!(matches!(
+            result,
+            Err(LoginError::DeviceFlow(DeviceFlowError::Expired))
+        ));
+    }
+}
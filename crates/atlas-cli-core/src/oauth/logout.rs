@@ -0,0 +1,306 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use thiserror::Error;
+use tokio::fs::remove_dir_all;
+
+use super::{send_form, OAuthConfig, OAuthRequestError, REVOKE_PATH};
+use crate::{
+    paths::token_cache_path,
+    profile::{
+        Auth, OAuth, ProfileFile, ProfileFileLoadError, ProfileFileSaveError, DEFAULT_PROFILE,
+    },
+};
+
+/// How long revoking the tokens of a profile may take before giving up on it.
+const REVOCATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The profiles to log out of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogoutTarget<'a> {
+    Profile(&'a str),
+    AllProfiles,
+}
+
+/// The outcome of logging out of a single profile.
+#[derive(Debug)]
+pub struct LoggedOut {
+    pub profile_name: String,
+    /// Result of revoking the OAuth tokens, `None` if the profile had no OAuth tokens.
+    pub revocation: Option<Result<(), OAuthRequestError>>,
+}
+
+#[derive(Error, Debug)]
+pub enum LogoutError {
+    #[error("profile '{0}' not found")]
+    ProfileNotFound(String),
+    #[error("failed to lock the profile file")]
+    Lock(#[source] std::io::Error),
+    #[error("failed to load the profile file")]
+    Load(#[from] ProfileFileLoadError),
+    #[error("failed to save the profile file")]
+    Save(#[from] ProfileFileSaveError),
+    #[error("failed to remove the cached tokens in {path}")]
+    RemoveTokenCache {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Revokes the refresh token and the access token at the authorization server (RFC 7009).
+///
+/// Both are revoked even if revoking the refresh token fails, returning the first error.
+pub async fn revoke(
+    http: &reqwest::Client,
+    config: &OAuthConfig,
+    tokens: &OAuth,
+) -> Result<(), OAuthRequestError> {
+    let url = config.endpoint(REVOKE_PATH);
+    let mut result = Ok(());
+    for (token, hint) in [
+        (tokens.refresh_token(), "refresh_token"),
+        (tokens.access_token(), "access_token"),
+    ] {
+        let form = [
+            ("client_id", config.client_id.as_str()),
+            ("token", token),
+            ("token_type_hint", hint),
+        ];
+        let revoked = send_form(http, url.clone(), &form).await;
+        result = result.and(revoked.map(drop));
+    }
+    result
+}
+
+/// Removes the credentials of the targeted profiles from the profile file at `profile_path`
+/// and their cached tokens from `state_path`.
+///
+/// Credentials are removed locally first. Revoking OAuth tokens is best-effort: failures are
+/// reported in the returned [`LoggedOut`] entries instead of failing the logout.
+pub async fn logout(
+    profile_path: impl AsRef<Path>,
+    state_path: impl AsRef<Path>,
+    target: LogoutTarget<'_>,
+) -> Result<Vec<LoggedOut>, LogoutError> {
+    let profile_path = profile_path.as_ref();
+    // Held until the credentials are removed, so that a concurrent refresh can't save them
+    // again in between.
+    let lock = ProfileFile::lock(profile_path)
+        .await
+        .map_err(LogoutError::Lock)?;
+    let mut profile_file = ProfileFile::load_or_default(profile_path).await?;
+
+    let profile_names = match target {
+        LogoutTarget::Profile(name) if profile_file.profile(name).is_none() => {
+            return Err(LogoutError::ProfileNotFound(name.to_string()))
+        }
+        LogoutTarget::Profile(name) => vec![name.to_string()],
+        LogoutTarget::AllProfiles => profile_file
+            .default_profile
+            .iter()
+            .map(|_| DEFAULT_PROFILE.to_string())
+            .chain(profile_file.profiles.keys().cloned())
+            .collect(),
+    };
+
+    // The tokens to revoke per profile, taken out of the profile file.
+    let mut cleared = Vec::with_capacity(profile_names.len());
+    for profile_name in profile_names {
+        let profile = match profile_name.as_str() {
+            DEFAULT_PROFILE => profile_file.default_profile.as_mut(),
+            name => profile_file.profiles.get_mut(name),
+        };
+        let tokens = match profile.and_then(|profile| profile.auth.take()) {
+            Some(Auth::OAuth(tokens)) => {
+                let profile = profile_file.profile(&profile_name).expect("profile exists");
+                Some((OAuthConfig::for_profile(profile), tokens))
+            }
+            Some(Auth::ApiKeys(_)) | None => None,
+        };
+        cleared.push((profile_name, tokens));
+    }
+
+    profile_file.save(profile_path).await?;
+    drop(lock);
+
+    for (profile_name, _) in &cleared {
+        // Nothing is cached for profiles whose names can't be a cache directory.
+        let Ok(path) = token_cache_path(state_path.as_ref(), profile_name) else {
+            continue;
+        };
+        match remove_dir_all(&path).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                return Err(LogoutError::RemoveTokenCache { path, source: e })
+            }
+            _ => {}
+        }
+    }
+
+    let http = reqwest::Client::builder()
+        .timeout(REVOCATION_TIMEOUT)
+        .build()
+        .expect("valid HTTP client");
+    let mut logged_out = Vec::with_capacity(cleared.len());
+    for (profile_name, tokens) in cleared {
+        let revocation = match tokens {
+            Some((config, tokens)) => Some(revoke(&http, &config, &tokens).await),
+            None => None,
+        };
+        logged_out.push(LoggedOut {
+            profile_name,
+            revocation,
+        });
+    }
+
+    Ok(logged_out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use atlas_mock::MockServer;
+    use url::Url;
+
+    use super::*;
+    use crate::profile::{ApiKeys, Profile};
+
+    async fn logged_in(server: &MockServer, path: &Path, profile_name: &str) -> OAuth {
+        let mut profile_file = ProfileFile::load_or_default(path).await.unwrap();
+        profile_file.profile_mut(profile_name).base_url = Some(server.base_url().clone());
+        profile_file.save(path).await.unwrap();
+
+        crate::oauth::login(path, profile_name, |authorization| {
+            server.approve_device(&authorization.user_code);
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn logout_profile() {
+        let server = MockServer::builder()
+            .device_interval(Duration::ZERO)
+            .start()
+            .await
+            .unwrap();
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("config.toml");
+        let state_path = directory.path().join("state");
+
+        let dev = logged_in(&server, &path, "dev").await;
+        let prod = logged_in(&server, &path, "prod").await;
+        let cache = token_cache_path(&state_path, "dev").unwrap();
+        tokio::fs::create_dir_all(&cache).await.unwrap();
+        tokio::fs::write(cache.join("token"), "cached")
+            .await
+            .unwrap();
+
+        let logged_out = logout(&path, &state_path, LogoutTarget::Profile("dev"))
+            .await
+            .unwrap();
+        assert_eq!(logged_out.len(), 1);
+        assert_eq!(logged_out[0].profile_name, "dev");
+        assert!(matches!(logged_out[0].revocation, Some(Ok(()))));
+
+        assert_eq!(
+            server.revoked_tokens(),
+            [dev.refresh_token(), dev.access_token()]
+        );
+        assert!(!cache.exists());
+
+        let profile_file = ProfileFile::load(&path).await.unwrap();
+        let profile = profile_file.profile("dev").unwrap();
+        assert!(profile.auth.is_none());
+        assert_eq!(profile.base_url.as_ref(), Some(server.base_url()));
+        assert!(matches!(
+            &profile_file.profile("prod").unwrap().auth,
+            Some(Auth::OAuth(tokens)) if *tokens == prod
+        ));
+    }
+
+    #[tokio::test]
+    async fn logout_all_profiles() {
+        let server = MockServer::builder()
+            .device_interval(Duration::ZERO)
+            .start()
+            .await
+            .unwrap();
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("config.toml");
+
+        logged_in(&server, &path, DEFAULT_PROFILE).await;
+        let mut profile_file = ProfileFile::load(&path).await.unwrap();
+        profile_file.profile_mut("keys").auth =
+            Some(Auth::ApiKeys(ApiKeys::new("public", "private")));
+        profile_file.save(&path).await.unwrap();
+
+        let logged_out = logout(&path, directory.path(), LogoutTarget::AllProfiles)
+            .await
+            .unwrap();
+        let names: Vec<_> = logged_out.iter().map(|l| l.profile_name.as_str()).collect();
+        assert_eq!(names, [DEFAULT_PROFILE, "keys"]);
+        assert!(matches!(logged_out[0].revocation, Some(Ok(()))));
+        assert!(logged_out[1].revocation.is_none());
+
+        let profile_file = ProfileFile::load(&path).await.unwrap();
+        assert!(profile_file.default_profile.unwrap().auth.is_none());
+        assert!(profile_file.profiles["keys"].auth.is_none());
+    }
+
+    #[tokio::test]
+    async fn logout_unknown_profile() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("config.toml");
+        let state_path = directory.path().join("state");
+
+        let mut profile_file = ProfileFile::default();
+        profile_file.profile_mut("dev").auth =
+            Some(Auth::ApiKeys(ApiKeys::new("public", "private")));
+        profile_file.save(&path).await.unwrap();
+        let contents = tokio::fs::read_to_string(&path).await.unwrap();
+        let cache = token_cache_path(&state_path, "dev").unwrap();
+        tokio::fs::create_dir_all(&cache).await.unwrap();
+
+        for profile_name in ["..", "prod"] {
+            let result = logout(&path, &state_path, LogoutTarget::Profile(profile_name)).await;
+            assert!(matches!(
+                result,
+                Err(LogoutError::ProfileNotFound(name)) if name == profile_name
+            ));
+        }
+
+        assert!(cache.exists());
+        assert_eq!(tokio::fs::read_to_string(&path).await.unwrap(), contents);
+    }
+
+    #[tokio::test]
+    async fn revocation_is_best_effort() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("config.toml");
+
+        // Nothing listens on port 9 (discard) on localhost.
+        let mut profile_file = ProfileFile::default();
+        profile_file.profiles.insert(
+            "dev".to_string(),
+            Profile {
+                base_url: Some(Url::parse("http://127.0.0.1:9/").unwrap()),
+                auth: Some(Auth::OAuth(OAuth::new("access", "refresh"))),
+                ..Default::default()
+            },
+        );
+        profile_file.save(&path).await.unwrap();
+
+        let logged_out = logout(&path, directory.path(), LogoutTarget::Profile("dev"))
+            .await
+            .unwrap();
+        assert!(matches!(logged_out[0].revocation, Some(Err(_))));
+
+        let profile_file = ProfileFile::load(&path).await.unwrap();
+        assert!(profile_file.profile("dev").unwrap().auth.is_none());
+    }
+}
@@ -0,0 +1,184 @@
+use serde::Deserialize;
+use thiserror::Error;
+use url::Url;
+
+use crate::profile::{OAuth, Profile, Service};
+
+mod device;
+mod logout;
+mod refresh;
+
+pub use device::*;
+pub use logout::*;
+pub use refresh::*;
+
+/// Client ID of the Atlas CLI, used when the profile doesn't set `client_id`.
+pub const DEFAULT_CLIENT_ID: &str = "0oabtxactgS3gHIR0297";
+pub const DEFAULT_SCOPES: &[&str] = &["openid", "profile", "offline_access"];
+
+const CLOUD_URL: &str = "https://cloud.mongodb.com/";
+const CLOUDGOV_URL: &str = "https://cloud.mongodbgov.com/";
+
+const AUTHORIZE_PATH: &str = "api/private/unauth/account/device/authorize";
+const TOKEN_PATH: &str = "api/private/unauth/account/device/token";
+const REVOKE_PATH: &str = "api/private/unauth/account/device/revoke";
+
+/// Where and as whom to talk to the Atlas authorization server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OAuthConfig {
+    pub base_url: Url,
+    pub client_id: String,
+    pub scopes: Vec<String>,
+}
+
+impl OAuthConfig {
+    pub fn new(base_url: Url) -> Self {
+        Self {
+            base_url,
+            client_id: DEFAULT_CLIENT_ID.to_string(),
+            scopes: DEFAULT_SCOPES.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Uses the profile's `base_url` and `client_id`, falling back to the defaults of the
+    /// profile's service.
+    pub fn for_profile(profile: &Profile) -> Self {
+        let base_url = profile.base_url.clone().unwrap_or_else(|| {
+            let url = match profile.service {
+                Some(Service::GovCloud) => CLOUDGOV_URL,
+                Some(Service::Cloud) | None => CLOUD_URL,
+            };
+            Url::parse(url).expect("valid default URL")
+        });
+
+        let mut config = Self::new(base_url);
+        if let Some(client_id) = &profile.client_id {
+            config.client_id = client_id.clone();
+        }
+        config
+    }
+
+    fn endpoint(&self, path: &str) -> Url {
+        let mut base_url = self.base_url.clone();
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        base_url.join(path).expect("valid endpoint path")
+    }
+
+    fn scope(&self) -> String {
+        self.scopes.join(" ")
+    }
+}
+
+/// A successful response of the token endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    /// Missing if the server doesn't rotate refresh tokens.
+    pub refresh_token: Option<String>,
+    pub token_type: String,
+    pub expires_in: Option<u64>,
+}
+
+impl TokenResponse {
+    /// The new tokens, keeping `refresh_token` if the response doesn't carry a new one.
+    pub fn into_oauth(self, refresh_token: &str) -> OAuth {
+        let refresh_token = self.refresh_token.as_deref().unwrap_or(refresh_token);
+        OAuth::new(self.access_token, refresh_token)
+    }
+}
+
+/// An RFC 6749 error response, e.g. `{"error": "authorization_pending"}`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+struct ErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum OAuthRequestError {
+    #[error("HTTP request failed")]
+    Request(#[from] reqwest::Error),
+    #[error("authorization server returned '{error}'")]
+    OAuth {
+        error: String,
+        description: Option<String>,
+    },
+    #[error("unexpected response with status {status}")]
+    UnexpectedResponse { status: u16, body: String },
+}
+
+/// Posts a form to an authorization server endpoint and returns the status and body of a
+/// successful response.
+async fn send_form(
+    http: &reqwest::Client,
+    url: Url,
+    form: &[(&str, &str)],
+) -> Result<(u16, String), OAuthRequestError> {
+    let response = http
+        .post(url)
+        .header("Accept", "application/json")
+        .form(form)
+        .send()
+        .await?;
+    let status = response.status().as_u16();
+    let body = response.text().await?;
+
+    if (200..300).contains(&status) {
+        return Ok((status, body));
+    }
+    if let Ok(ErrorResponse {
+        error,
+        error_description,
+    }) = serde_json::from_str(&body)
+    {
+        return Err(OAuthRequestError::OAuth {
+            error,
+            description: error_description,
+        });
+    }
+
+    Err(OAuthRequestError::UnexpectedResponse { status, body })
+}
+
+/// Posts a form to an authorization server endpoint and decodes the JSON response.
+async fn post_form<T: for<'de> Deserialize<'de>>(
+    http: &reqwest::Client,
+    url: Url,
+    form: &[(&str, &str)],
+) -> Result<T, OAuthRequestError> {
+    let (status, body) = send_form(http, url, form).await?;
+    serde_json::from_str(&body).map_err(|_| OAuthRequestError::UnexpectedResponse { status, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_for_profile() {
+        let config = OAuthConfig::for_profile(&Profile::default());
+        assert_eq!(config.base_url.as_str(), CLOUD_URL);
+        assert_eq!(config.client_id, DEFAULT_CLIENT_ID);
+        assert_eq!(config.scope(), "openid profile offline_access");
+
+        let profile = Profile {
+            service: Some(Service::GovCloud),
+            client_id: Some("client".to_string()),
+            ..Default::default()
+        };
+        let config = OAuthConfig::for_profile(&profile);
+        assert_eq!(config.base_url.as_str(), CLOUDGOV_URL);
+        assert_eq!(config.client_id, "client");
+    }
+
+    #[test]
+    fn endpoint_keeps_base_path() {
+        let config = OAuthConfig::new(Url::parse("http://localhost:8080/prefix").unwrap());
+        assert_eq!(
+            config.endpoint(TOKEN_PATH).as_str(),
+            "http://localhost:8080/prefix/api/private/unauth/account/device/token"
+        );
+    }
+}
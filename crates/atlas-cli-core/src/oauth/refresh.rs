@@ -0,0 +1,370 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use atlas_core::client::{TokenFuture, TokenProvider};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+use super::{post_form, OAuthConfig, OAuthRequestError, TokenResponse, TOKEN_PATH};
+use crate::profile::{Auth, OAuth, ProfileFile, ProfileFileLoadError, ProfileFileSaveError};
+
+const REFRESH_TOKEN_GRANT: &str = "refresh_token";
+
+/// Access tokens expiring within this window are refreshed before they are used.
+pub const REFRESH_SKEW: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct Claims {
+    exp: u64,
+}
+
+/// Decodes the expiry of a JWT access token without verifying its signature. Returns `None`
+/// if the token isn't a JWT or doesn't carry an `exp` claim.
+pub fn expires_at(access_token: &str) -> Option<SystemTime> {
+    let claims = access_token.split('.').nth(1)?;
+    let claims = URL_SAFE_NO_PAD.decode(claims.trim_end_matches('=')).ok()?;
+    let Claims { exp } = serde_json::from_slice(&claims).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(exp))
+}
+
+fn expires_soon(access_token: &str) -> bool {
+    expires_at(access_token)
+        .is_some_and(|expires_at| expires_at <= SystemTime::now() + REFRESH_SKEW)
+}
+
+#[derive(Error, Debug)]
+pub enum OpenSessionError {
+    #[error("failed to load the profile file")]
+    Load(#[from] ProfileFileLoadError),
+    #[error("profile '{0}' is not logged in using OAuth")]
+    NotLoggedIn(String),
+}
+
+#[derive(Error, Debug)]
+pub enum RefreshError {
+    #[error("profile '{0}' was logged out")]
+    LoggedOut(String),
+    #[error("failed to refresh the access token")]
+    Request(#[from] OAuthRequestError),
+    #[error("failed to lock the profile file")]
+    Lock(#[source] std::io::Error),
+    #[error("failed to load the profile file")]
+    Load(#[from] ProfileFileLoadError),
+    #[error("failed to save the profile file")]
+    Save(#[from] ProfileFileSaveError),
+}
+
+/// The OAuth tokens of a profile, refreshed when needed and persisted to the profile file.
+///
+/// Refreshes are serialized: concurrent callers wait for the refresh in progress and then
+/// use its result, so the refresh token (which the server rotates) is only used once.
+#[derive(Debug)]
+pub struct OAuthSession {
+    config: OAuthConfig,
+    http: reqwest::Client,
+    path: PathBuf,
+    profile_name: String,
+    tokens: Mutex<OAuth>,
+}
+
+impl OAuthSession {
+    /// Opens the session of the profile `profile_name` in the profile file at `path`.
+    pub async fn open(
+        path: impl AsRef<Path>,
+        profile_name: &str,
+    ) -> Result<Self, OpenSessionError> {
+        let path = path.as_ref();
+        let profile_file = ProfileFile::load(path).await?;
+        let profile = profile_file.profile(profile_name);
+        let Some(Auth::OAuth(tokens)) = profile.and_then(|profile| profile.auth.clone()) else {
+            return Err(OpenSessionError::NotLoggedIn(profile_name.to_string()));
+        };
+
+        Ok(Self {
+            config: OAuthConfig::for_profile(profile.expect("profile has OAuth tokens")),
+            http: reqwest::Client::new(),
+            path: path.to_path_buf(),
+            profile_name: profile_name.to_string(),
+            tokens: Mutex::new(tokens),
+        })
+    }
+
+    pub fn config(&self) -> &OAuthConfig {
+        &self.config
+    }
+
+    /// The current tokens, without refreshing them.
+    pub async fn tokens(&self) -> OAuth {
+        self.tokens.lock().await.clone()
+    }
+
+    /// Returns the access token, refreshing it first if it is about to expire.
+    pub async fn access_token(&self) -> Result<String, RefreshError> {
+        let mut tokens = self.tokens.lock().await;
+        if expires_soon(tokens.access_token()) {
+            self.refresh_locked(&mut tokens).await?;
+        }
+        Ok(tokens.access_token().to_string())
+    }
+
+    /// Refreshes the tokens after the API rejected `rejected`, unless another request
+    /// already replaced it.
+    pub async fn refresh(&self, rejected: &str) -> Result<String, RefreshError> {
+        let mut tokens = self.tokens.lock().await;
+        if tokens.access_token() == rejected {
+            self.refresh_locked(&mut tokens).await?;
+        }
+        Ok(tokens.access_token().to_string())
+    }
+
+    async fn refresh_locked(&self, tokens: &mut OAuth) -> Result<(), RefreshError> {
+        // Held until the refreshed tokens are saved, so other processes refreshing the same
+        // profile wait for them instead of using a refresh token that is being rotated.
+        let _lock = ProfileFile::lock(&self.path)
+            .await
+            .map_err(RefreshError::Lock)?;
+
+        // Another process may have refreshed the tokens already, which would have rotated
+        // the refresh token we hold, or logged the profile out, whose credentials mustn't be
+        // brought back.
+        let mut profile_file = ProfileFile::load_or_default(&self.path).await?;
+        let Some(Auth::OAuth(stored)) = profile_file
+            .profile(&self.profile_name)
+            .and_then(|profile| profile.auth.as_ref())
+        else {
+            return Err(RefreshError::LoggedOut(self.profile_name.clone()));
+        };
+        if stored != tokens && !expires_soon(stored.access_token()) {
+            *tokens = stored.clone();
+            return Ok(());
+        }
+
+        let form = [
+            ("grant_type", REFRESH_TOKEN_GRANT),
+            ("client_id", self.config.client_id.as_str()),
+            ("refresh_token", tokens.refresh_token()),
+        ];
+        let response: TokenResponse =
+            post_form(&self.http, self.config.endpoint(TOKEN_PATH), &form).await?;
+        *tokens = response.into_oauth(tokens.refresh_token());
+
+        profile_file.profile_mut(&self.profile_name).auth = Some(Auth::OAuth(tokens.clone()));
+        profile_file.save(&self.path).await?;
+
+        Ok(())
+    }
+}
+
+impl TokenProvider for OAuthSession {
+    fn access_token(&self) -> TokenFuture<'_> {
+        Box::pin(async move { Ok(OAuthSession::access_token(self).await?) })
+    }
+
+    fn refresh<'a>(&'a self, rejected: &'a str) -> TokenFuture<'a> {
+        Box::pin(async move { Ok(OAuthSession::refresh(self, rejected).await?) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use atlas_core::client::{Client, Credentials, HttpRequest, Method};
+    use atlas_mock::MockServer;
+    use tokio::task::JoinSet;
+
+    use super::*;
+    use crate::profile::Profile;
+
+    fn jwt(claims: &str) -> String {
+        format!("e30.{}.", URL_SAFE_NO_PAD.encode(claims))
+    }
+
+    #[test]
+    fn decode_expiry() {
+        assert_eq!(
+            expires_at(&jwt(r#"{"exp":1700000000,"sub":"user"}"#)),
+            Some(UNIX_EPOCH + Duration::from_secs(1700000000))
+        );
+        assert_eq!(expires_at(&jwt(r#"{"sub":"user"}"#)), None);
+        assert_eq!(expires_at("opaque"), None);
+
+        assert!(expires_soon(&jwt(r#"{"exp":1700000000}"#)));
+        assert!(!expires_soon("opaque"));
+    }
+
+    /// Logs a profile in through the mock's device flow.
+    async fn logged_in(server: &MockServer) -> (tempfile::TempDir, PathBuf) {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("config.toml");
+
+        let mut profile_file = ProfileFile::default();
+        profile_file.profiles.insert(
+            "dev".to_string(),
+            Profile {
+                base_url: Some(server.base_url().clone()),
+                ..Default::default()
+            },
+        );
+        profile_file.save(&path).await.unwrap();
+
+        crate::oauth::login(&path, "dev", |authorization| {
+            server.approve_device(&authorization.user_code);
+        })
+        .await
+        .unwrap();
+
+        (directory, path)
+    }
+
+    async fn stored_tokens(path: &Path) -> OAuth {
+        match ProfileFile::load(path).await.unwrap().profile("dev") {
+            Some(Profile {
+                auth: Some(Auth::OAuth(tokens)),
+                ..
+            }) => tokens.clone(),
+            _ => panic!("profile has no OAuth tokens"),
+        }
+    }
+
+    async fn get_orgs(client: &Client) -> u16 {
+        let url = client.base_url().join("api/atlas/v2/orgs").unwrap();
+        client
+            .send_http(HttpRequest::new(Method::Get, url))
+            .await
+            .unwrap()
+            .status
+    }
+
+    #[tokio::test]
+    async fn refresh_before_expiry() {
+        let server = MockServer::builder()
+            .require_auth()
+            .device_interval(Duration::ZERO)
+            .access_token_lifetime(Duration::from_secs(30))
+            .start()
+            .await
+            .unwrap();
+        let (_directory, path) = logged_in(&server).await;
+        let session = Arc::new(OAuthSession::open(&path, "dev").await.unwrap());
+        let initial = session.tokens().await;
+
+        let client = Client::new(server.base_url().clone())
+            .with_credentials(Credentials::OAuth(session.clone()));
+        assert_eq!(get_orgs(&client).await, 200);
+        assert_eq!(server.refresh_count(), 1);
+
+        let refreshed = session.tokens().await;
+        assert_ne!(refreshed.access_token(), initial.access_token());
+        assert_ne!(refreshed.refresh_token(), initial.refresh_token());
+        assert_eq!(stored_tokens(&path).await, refreshed);
+    }
+
+    #[tokio::test]
+    async fn keep_refresh_token_without_rotation() {
+        let server = MockServer::builder()
+            .require_auth()
+            .device_interval(Duration::ZERO)
+            .rotate_refresh_tokens(false)
+            .start()
+            .await
+            .unwrap();
+        let (_directory, path) = logged_in(&server).await;
+        let session = OAuthSession::open(&path, "dev").await.unwrap();
+        let initial = session.tokens().await;
+
+        for refreshes in 1..=2 {
+            let rejected = session.tokens().await.access_token().to_string();
+            session.refresh(&rejected).await.unwrap();
+            assert_eq!(server.refresh_count(), refreshes);
+        }
+
+        let refreshed = session.tokens().await;
+        assert_ne!(refreshed.access_token(), initial.access_token());
+        assert_eq!(refreshed.refresh_token(), initial.refresh_token());
+        assert_eq!(stored_tokens(&path).await, refreshed);
+    }
+
+    #[tokio::test]
+    async fn refresh_after_logout() {
+        let server = MockServer::builder()
+            .require_auth()
+            .device_interval(Duration::ZERO)
+            .start()
+            .await
+            .unwrap();
+        let (directory, path) = logged_in(&server).await;
+        let session = OAuthSession::open(&path, "dev").await.unwrap();
+
+        crate::oauth::logout(
+            &path,
+            directory.path(),
+            crate::oauth::LogoutTarget::Profile("dev"),
+        )
+        .await
+        .unwrap();
+
+        let rejected = session.tokens().await.access_token().to_string();
+        assert!(matches!(
+            session.refresh(&rejected).await,
+            Err(RefreshError::LoggedOut(name)) if name == "dev"
+        ));
+        assert_eq!(server.refresh_count(), 0);
+        let profile_file = ProfileFile::load(&path).await.unwrap();
+        assert!(profile_file.profile("dev").unwrap().auth.is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn refresh_after_unauthorized() {
+        let server = MockServer::builder()
+            .require_auth()
+            .device_interval(Duration::ZERO)
+            .start()
+            .await
+            .unwrap();
+        let (_directory, path) = logged_in(&server).await;
+        let session = Arc::new(OAuthSession::open(&path, "dev").await.unwrap());
+        let client = Arc::new(
+            Client::new(server.base_url().clone())
+                .with_credentials(Credentials::OAuth(session.clone())),
+        );
+
+        assert_eq!(get_orgs(&client).await, 200);
+        assert_eq!(server.refresh_count(), 0);
+
+        // The server revokes the token, e.g. because it was issued before a password change.
+        server.remove_access_token(session.tokens().await.access_token());
+        let mut requests = JoinSet::new();
+        for _ in 0..8 {
+            let client = client.clone();
+            requests.spawn(async move { get_orgs(&client).await });
+        }
+        while let Some(status) = requests.join_next().await {
+            assert_eq!(status.unwrap(), 200);
+        }
+        assert_eq!(server.refresh_count(), 1);
+        assert_eq!(stored_tokens(&path).await, session.tokens().await);
+    }
+
+    #[tokio::test]
+    async fn adopt_tokens_refreshed_by_another_session() {
+        let server = MockServer::builder()
+            .require_auth()
+            .device_interval(Duration::ZERO)
+            .start()
+            .await
+            .unwrap();
+        let (_directory, path) = logged_in(&server).await;
+        let first = OAuthSession::open(&path, "dev").await.unwrap();
+        let second = OAuthSession::open(&path, "dev").await.unwrap();
+
+        let rejected = first.tokens().await.access_token().to_string();
+        let token = first.refresh(&rejected).await.unwrap();
+        assert_eq!(second.refresh(&rejected).await.unwrap(), token);
+        assert_eq!(server.refresh_count(), 1);
+    }
+}
@@ -1,10 +1,11 @@
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
-use dirs::config_dir;
+use dirs::{config_dir, data_local_dir, state_dir};
 use thiserror::Error;
 
 pub struct Paths {
     base_path: PathBuf,
+    state_path: PathBuf,
 }
 
 impl Paths {
@@ -12,14 +13,56 @@ impl Paths {
         let config_dir = config_dir().ok_or(NewPathsError::NotFound)?;
         let base_path = config_dir.join("atlascli");
 
-        Ok(Self { base_path })
+        // Only some platforms have a dedicated state directory.
+        let state_path = match state_dir().or_else(data_local_dir) {
+            Some(state_dir) => state_dir.join("atlascli"),
+            None => base_path.join("state"),
+        };
+
+        Ok(Self {
+            base_path,
+            state_path,
+        })
     }
 
     pub fn profile_path(&self) -> PathBuf {
         self.base_path.join("config.toml")
     }
+
+    /// Directory for data which isn't configuration, like cached tokens.
+    pub fn state_path(&self) -> PathBuf {
+        self.state_path.clone()
+    }
+
+    /// Directory where tokens derived from the credentials of `profile_name` are cached.
+    ///
+    /// Fails for profile names which aren't a single path component, like `..`, as those
+    /// would point outside of the token cache.
+    pub fn token_cache_path(&self, profile_name: &str) -> Result<PathBuf, InvalidProfileNameError> {
+        token_cache_path(&self.state_path, profile_name)
+    }
+}
+
+pub(crate) fn token_cache_path(
+    state_path: &Path,
+    profile_name: &str,
+) -> Result<PathBuf, InvalidProfileNameError> {
+    let mut components = Path::new(profile_name).components();
+    let is_single_component = matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    );
+    // `components` ignores trailing separators and normalizes `a/./b`
+    if !is_single_component || profile_name.contains(['/', '\\']) {
+        return Err(InvalidProfileNameError(profile_name.to_string()));
+    }
+    Ok(state_path.join("tokens").join(profile_name))
 }
 
+#[derive(Error, Debug)]
+#[error("'{0}' is not a valid profile name")]
+pub struct InvalidProfileNameError(pub String);
+
 #[derive(Error, Debug)]
 pub enum NewPathsError {
     #[error("Failed to find user config directory")]
@@ -36,6 +79,7 @@ mod tests {
     fn test_profile_path() {
         let paths = Paths {
             base_path: PathBuf::from_str("/home/user/.config/atlascli").unwrap(),
+            state_path: PathBuf::from_str("/home/user/.local/state/atlascli").unwrap(),
         };
 
         let expected = PathBuf::from_str("/home/user/.config/atlascli/config.toml").unwrap();
@@ -43,4 +87,29 @@ mod tests {
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn test_token_cache_path() {
+        let paths = Paths {
+            base_path: PathBuf::from_str("/home/user/.config/atlascli").unwrap(),
+            state_path: PathBuf::from_str("/home/user/.local/state/atlascli").unwrap(),
+        };
+
+        let expected = PathBuf::from_str("/home/user/.local/state/atlascli/tokens/dev").unwrap();
+        let actual = paths.token_cache_path("dev").unwrap();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_invalid_token_cache_path() {
+        let state_path = Path::new("/home/user/.local/state/atlascli");
+        for profile_name in ["", ".", "..", "/", "/etc", "dev/..", "dev/", "a\\b"] {
+            assert!(
+                token_cache_path(state_path, profile_name).is_err(),
+                "{profile_name:?} is accepted"
+            );
+        }
+        assert!(token_cache_path(state_path, "dev.old").is_ok());
+    }
 }
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+#[allow(clippy::module_inception)]
 mod profile;
 
 pub use profile::*;
@@ -58,12 +59,46 @@ pub struct ApiKeys {
     private: String,
 }
 
+impl ApiKeys {
+    pub fn new(public: impl Into<String>, private: impl Into<String>) -> Self {
+        Self {
+            public: public.into(),
+            private: private.into(),
+        }
+    }
+
+    pub fn public(&self) -> &str {
+        &self.public
+    }
+
+    pub fn private(&self) -> &str {
+        &self.private
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct OAuth {
     access_token: String,
     refresh_token: String,
 }
 
+impl OAuth {
+    pub fn new(access_token: impl Into<String>, refresh_token: impl Into<String>) -> Self {
+        Self {
+            access_token: access_token.into(),
+            refresh_token: refresh_token.into(),
+        }
+    }
+
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    pub fn refresh_token(&self) -> &str {
+        &self.refresh_token
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
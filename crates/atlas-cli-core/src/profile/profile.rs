@@ -2,9 +2,10 @@ use serde::Deserialize;
 use std::{
     collections::BTreeMap,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 use thiserror::Error;
-use tokio::fs::{create_dir_all, read_to_string, write};
+use tokio::fs::{create_dir_all, read_to_string, rename, write};
 use toml::Table;
 use url::Url;
 
@@ -12,6 +13,9 @@ use super::{ApiKeys, Auth, OAuth, Output, Service};
 
 pub const DEFAULT_PROFILE: &str = "default";
 
+/// Distinguishes the temporary files of concurrent saves within this process.
+static TEMPORARY_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 #[derive(Clone, Debug, Default)]
 pub struct ProfileFile {
     pub mongosh_path: Option<String>,
@@ -39,6 +43,12 @@ pub struct Profile {
 #[derive(Clone, Debug)]
 pub struct AdditionalProperty(toml::Value);
 
+/// An exclusive advisory lock on a profile file, released when dropped.
+#[derive(Debug)]
+pub struct ProfileFileLock {
+    _file: std::fs::File,
+}
+
 impl ProfileFile {
     pub async fn load(path: impl AsRef<Path>) -> Result<Self, ProfileFileLoadError> {
         let yaml_string = read_to_string(path).await?;
@@ -46,16 +56,74 @@ impl ProfileFile {
         Ok(ProfileFile::try_from(yaml_value)?)
     }
 
+    /// Loads the profile file, or returns an empty one if it doesn't exist yet.
+    pub async fn load_or_default(path: impl AsRef<Path>) -> Result<Self, ProfileFileLoadError> {
+        match Self::load(path).await {
+            Err(ProfileFileLoadError::Read(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            result => result,
+        }
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        if name == DEFAULT_PROFILE {
+            self.default_profile.as_ref()
+        } else {
+            self.profiles.get(name)
+        }
+    }
+
+    /// Returns the profile with the given name, creating an empty one if it doesn't exist.
+    pub fn profile_mut(&mut self, name: &str) -> &mut Profile {
+        if name == DEFAULT_PROFILE {
+            self.default_profile.get_or_insert_with(Profile::default)
+        } else {
+            self.profiles.entry(name.to_string()).or_default()
+        }
+    }
+
+    /// Locks the profile file at `path` for a read-modify-write, waiting while another process
+    /// holds the lock. The lock is taken on a `.lock` file next to it, as saving replaces the
+    /// profile file itself.
+    pub async fn lock(path: impl AsRef<Path>) -> std::io::Result<ProfileFileLock> {
+        let path = path.as_ref();
+        if let Some(directory_path) = path.parent() {
+            create_dir_all(directory_path).await?;
+        }
+
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(lock_path)?;
+            file.lock()?;
+            Ok(ProfileFileLock { _file: file })
+        })
+        .await
+        .expect("locking the profile file panicked")
+    }
+
+    /// Writes the profile file atomically: readers either see the old or the new contents,
+    /// never a partially written file.
     pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), ProfileFileSaveError> {
         let toml_table: toml::Table = self.clone().into();
         let toml = toml::to_string_pretty(&toml_table)?;
 
         let path = path.as_ref();
-        let mut directory_path = PathBuf::try_from(path).expect("error is of type Infallible");
+        let mut directory_path = PathBuf::from(path);
         directory_path.pop();
 
         create_dir_all(directory_path).await?;
-        write(path, toml).await?;
+
+        let mut temporary_path = path.as_os_str().to_owned();
+        let counter = TEMPORARY_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        temporary_path.push(format!(".{}.{counter}.tmp", std::process::id()));
+        write(&temporary_path, toml).await?;
+        rename(&temporary_path, path).await?;
 
         Ok(())
     }
@@ -121,10 +189,12 @@ impl TryFrom<toml::Table> for ProfileFile {
     type Error = ProfileFileTryFromTomlError;
 
     fn try_from(mut value: toml::Table) -> Result<Self, Self::Error> {
-        let mut file = Self::default();
-        file.mongosh_path = remove_entry(&mut value, "mongosh_path")?; //: Option<String>,
-        file.telemetry_enabled = remove_entry(&mut value, "telemetry_enabled")?; //: Option<bool>,
-        file.skip_update_check = remove_entry(&mut value, "skip_update_check")?; //: Option<bool>,
+        let mut file = Self {
+            mongosh_path: remove_entry(&mut value, "mongosh_path")?,
+            telemetry_enabled: remove_entry(&mut value, "telemetry_enabled")?,
+            skip_update_check: remove_entry(&mut value, "skip_update_check")?,
+            ..Default::default()
+        };
 
         for (key, value) in value {
             match value {
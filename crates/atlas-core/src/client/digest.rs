@@ -0,0 +1,198 @@
+use md5::{Digest, Md5};
+use rand::Rng;
+use thiserror::Error;
+
+/// A parsed `WWW-Authenticate: Digest ...` challenge.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DigestChallenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    qop_auth: bool,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DigestChallengeError {
+    #[error("the challenge doesn't use the Digest scheme")]
+    NotDigest,
+    #[error("the challenge is missing '{0}'")]
+    MissingParameter(&'static str),
+    #[error("unsupported digest algorithm '{0}'")]
+    UnsupportedAlgorithm(String),
+}
+
+impl DigestChallenge {
+    pub(crate) fn parse(header: &str) -> Result<Self, DigestChallengeError> {
+        let params = header
+            .trim()
+            .strip_prefix("Digest ")
+            .ok_or(DigestChallengeError::NotDigest)?;
+
+        let mut realm = None;
+        let mut nonce = None;
+        let mut opaque = None;
+        let mut qop_auth = false;
+
+        for (key, value) in split_params(params) {
+            match key.to_ascii_lowercase().as_str() {
+                "realm" => realm = Some(value),
+                "nonce" => nonce = Some(value),
+                "opaque" => opaque = Some(value),
+                "qop" => qop_auth = value.split(',').any(|qop| qop.trim() == "auth"),
+                "algorithm" if !value.eq_ignore_ascii_case("MD5") => {
+                    return Err(DigestChallengeError::UnsupportedAlgorithm(value))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            realm: realm.ok_or(DigestChallengeError::MissingParameter("realm"))?,
+            nonce: nonce.ok_or(DigestChallengeError::MissingParameter("nonce"))?,
+            opaque,
+            qop_auth,
+        })
+    }
+
+    /// Builds the `Authorization` header value answering this challenge.
+    pub(crate) fn authorization(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+    ) -> String {
+        let cnonce = format!("{:016x}", rand::thread_rng().gen::<u64>());
+        self.authorization_with_cnonce(username, password, method, uri, &cnonce)
+    }
+
+    fn authorization_with_cnonce(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> String {
+        const NONCE_COUNT: &str = "00000001";
+
+        let ha1 = md5_hex(&format!("{username}:{}:{password}", self.realm));
+        let ha2 = md5_hex(&format!("{method}:{uri}"));
+
+        let mut header = format!(
+            r#"Digest username="{username}", realm="{}", nonce="{}", uri="{uri}""#,
+            self.realm, self.nonce
+        );
+
+        if self.qop_auth {
+            let response = md5_hex(&format!(
+                "{ha1}:{}:{NONCE_COUNT}:{cnonce}:auth:{ha2}",
+                self.nonce
+            ));
+            header.push_str(&format!(
+                r#", qop=auth, nc={NONCE_COUNT}, cnonce="{cnonce}", response="{response}""#
+            ));
+        } else {
+            let response = md5_hex(&format!("{ha1}:{}:{ha2}", self.nonce));
+            header.push_str(&format!(r#", response="{response}""#));
+        }
+
+        header.push_str(", algorithm=MD5");
+        if let Some(opaque) = &self.opaque {
+            header.push_str(&format!(r#", opaque="{opaque}""#));
+        }
+
+        header
+    }
+}
+
+pub fn md5_hex(input: &str) -> String {
+    Md5::digest(input.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Splits `key="value", key=value` lists, honouring commas inside quotes.
+pub fn split_params(params: &str) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    let mut rest = params.trim();
+
+    while !rest.is_empty() {
+        let Some((key, after_key)) = rest.split_once('=') else {
+            break;
+        };
+        let after_key = after_key.trim_start();
+
+        let (value, remaining) = match after_key.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((value, remaining)) => (value, remaining),
+                None => (quoted, ""),
+            },
+            None => after_key
+                .split_once(',')
+                .map_or((after_key, ""), |(value, remaining)| (value, remaining)),
+        };
+
+        result.push((key.trim().to_string(), value.trim().to_string()));
+        rest = remaining.trim_start().trim_start_matches(',').trim_start();
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_atlas_challenge() {
+        let challenge = DigestChallenge::parse(
+            r#"Digest realm="MMS Public API", domain="", nonce="abc,123", algorithm=MD5, qop="auth", stale=false"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            challenge,
+            DigestChallenge {
+                realm: "MMS Public API".to_string(),
+                nonce: "abc,123".to_string(),
+                opaque: None,
+                qop_auth: true,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_rejects_other_schemes() {
+        assert_eq!(
+            DigestChallenge::parse(r#"Bearer realm="atlas""#),
+            Err(DigestChallengeError::NotDigest)
+        );
+        assert_eq!(
+            DigestChallenge::parse(r#"Digest nonce="abc""#),
+            Err(DigestChallengeError::MissingParameter("realm"))
+        );
+    }
+
+    #[test]
+    fn rfc_2617_example() {
+        let challenge = DigestChallenge {
+            realm: "testrealm@host.com".to_string(),
+            nonce: "dcd98b7102dd2f0e8b11d0f600bfb0c093".to_string(),
+            opaque: Some("5ccc069c403ebaf9f0171e9517f40e41".to_string()),
+            qop_auth: true,
+        };
+
+        let header = challenge.authorization_with_cnonce(
+            "Mufasa",
+            "Circle Of Life",
+            "GET",
+            "/dir/index.html",
+            "0a4f113b",
+        );
+
+        assert!(header.contains(r#"response="6629fae49393a05397450978507c4ef1""#));
+        assert!(header.contains(r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#));
+    }
+}
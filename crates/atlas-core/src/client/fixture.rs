@@ -0,0 +1,311 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::transport::{HttpRequest, HttpResponse, Method, Transport, TransportError};
+
+/// Environment variable selecting the [`FixtureMode`], either `record` or `replay`.
+pub const FIXTURE_MODE_ENV: &str = "ATLAS_FIXTURES";
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Headers which carry credentials or digest handshake state.
+const SECRET_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "www-authenticate",
+    "authentication-info",
+    "cookie",
+    "set-cookie",
+];
+
+/// JSON properties which are replaced in recorded request and response bodies.
+const SECRET_BODY_FIELDS: &[&str] = &[
+    "password",
+    "privateKey",
+    "apiKey",
+    "access_token",
+    "refresh_token",
+    "id_token",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Forward requests to the inner transport and write every exchange to the fixture file.
+    Record,
+    /// Answer requests from the fixture file without touching the inner transport.
+    Replay,
+}
+
+impl FixtureMode {
+    /// Reads the mode from [`FIXTURE_MODE_ENV`], defaulting to [`FixtureMode::Replay`]
+    /// so tests stay offline unless recording was explicitly requested.
+    pub fn from_env() -> Self {
+        match std::env::var(FIXTURE_MODE_ENV) {
+            Ok(value) if value.eq_ignore_ascii_case("record") => FixtureMode::Record,
+            _ => FixtureMode::Replay,
+        }
+    }
+}
+
+/// A transport which records request/response pairs to a fixture file, or replays them.
+///
+/// Requests are matched on method, path and the sorted, decoded query pairs, so the
+/// order in which an `AsUrl` implementation appends parameters doesn't matter. Each
+/// recorded exchange is replayed at most once, in recording order.
+///
+/// Digest challenges (`401` with a `WWW-Authenticate: Digest` header) are not recorded:
+/// on replay the first, unauthenticated attempt is answered with the final response.
+pub struct FixtureTransport<T> {
+    inner: T,
+    path: PathBuf,
+    mode: FixtureMode,
+    state: Mutex<FixtureState>,
+}
+
+#[derive(Debug, Default)]
+struct FixtureState {
+    fixture: Fixture,
+    used: Vec<bool>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: HttpResponse,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>,
+}
+
+impl RecordedRequest {
+    fn matches(&self, method: Method, path: &str, query: &[(String, String)]) -> bool {
+        self.method == method && self.path == path && self.query == query
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum FixtureError {
+    #[error("failed to access fixture file '{path}'")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("fixture file '{path}' is invalid")]
+    Format {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("no unused recording for {method} {path} in '{fixture}'")]
+    NoMatch {
+        method: Method,
+        path: String,
+        fixture: PathBuf,
+    },
+}
+
+impl<T> FixtureTransport<T> {
+    /// Creates a fixture transport backed by the file at `path`.
+    ///
+    /// In [`FixtureMode::Replay`] the file must exist. In [`FixtureMode::Record`] any
+    /// existing recordings are discarded.
+    pub fn new(inner: T, path: impl AsRef<Path>, mode: FixtureMode) -> Result<Self, FixtureError> {
+        let path = path.as_ref().to_path_buf();
+
+        let fixture = match mode {
+            FixtureMode::Record => Fixture::default(),
+            FixtureMode::Replay => {
+                let contents = fs::read_to_string(&path).map_err(|source| FixtureError::Io {
+                    path: path.clone(),
+                    source,
+                })?;
+                serde_json::from_str(&contents).map_err(|source| FixtureError::Format {
+                    path: path.clone(),
+                    source,
+                })?
+            }
+        };
+
+        let used = vec![false; fixture.interactions.len()];
+
+        Ok(Self {
+            inner,
+            path,
+            mode,
+            state: Mutex::new(FixtureState { fixture, used }),
+        })
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn replay(&self, request: &HttpRequest) -> Result<HttpResponse, FixtureError> {
+        let path = request.url.path();
+        let query = normalized_query(request);
+
+        let mut state = self.state.lock().expect("fixture state poisoned");
+        let FixtureState { fixture, used } = &mut *state;
+
+        let index = fixture
+            .interactions
+            .iter()
+            .zip(used.iter())
+            .position(|(interaction, used)| {
+                !used && interaction.request.matches(request.method, path, &query)
+            })
+            .ok_or_else(|| FixtureError::NoMatch {
+                method: request.method,
+                path: path.to_string(),
+                fixture: self.path.clone(),
+            })?;
+
+        used[index] = true;
+        Ok(fixture.interactions[index].response.clone())
+    }
+
+    fn record(&self, request: &HttpRequest, response: &HttpResponse) -> Result<(), FixtureError> {
+        if is_digest_challenge(response) {
+            return Ok(());
+        }
+
+        let interaction = Interaction {
+            request: RecordedRequest {
+                method: request.method,
+                path: request.url.path().to_string(),
+                query: normalized_query(request),
+                headers: request
+                    .headers
+                    .iter()
+                    .map(|(name, value)| (name.to_ascii_lowercase(), scrub_header(name, value)))
+                    .collect(),
+                body: request.body.as_deref().map(scrub_body),
+            },
+            response: HttpResponse {
+                status: response.status,
+                headers: response
+                    .headers
+                    .iter()
+                    .map(|(name, value)| (name.clone(), scrub_header(name, value)))
+                    .collect(),
+                body: scrub_body(&response.body),
+            },
+        };
+
+        let mut state = self.state.lock().expect("fixture state poisoned");
+        state.fixture.interactions.push(interaction);
+        state.used.push(true);
+
+        let json = serde_json::to_string_pretty(&state.fixture).map_err(|source| {
+            FixtureError::Format {
+                path: self.path.clone(),
+                source,
+            }
+        })?;
+
+        let io_error = |source| FixtureError::Io {
+            path: self.path.clone(),
+            source,
+        };
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory).map_err(io_error)?;
+        }
+        fs::write(&self.path, json).map_err(io_error)?;
+
+        Ok(())
+    }
+}
+
+impl<T: Transport> Transport for FixtureTransport<T> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        match self.mode {
+            FixtureMode::Replay => Ok(self.replay(&request)?),
+            FixtureMode::Record => {
+                let response = self.inner.send(request.clone()).await?;
+                self.record(&request, &response)?;
+                Ok(response)
+            }
+        }
+    }
+}
+
+fn normalized_query(request: &HttpRequest) -> Vec<(String, String)> {
+    let mut pairs: Vec<_> = request
+        .url
+        .query_pairs()
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    pairs.sort();
+    pairs
+}
+
+fn is_digest_challenge(response: &HttpResponse) -> bool {
+    response.status == 401
+        && response
+            .header("www-authenticate")
+            .is_some_and(|value| value.trim_start().starts_with("Digest"))
+}
+
+fn scrub_header(name: &str, value: &str) -> String {
+    if SECRET_HEADERS
+        .iter()
+        .any(|secret| secret.eq_ignore_ascii_case(name))
+    {
+        REDACTED.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn scrub_body(body: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            scrub_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => body.to_string(),
+    }
+}
+
+fn scrub_value(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_BODY_FIELDS.contains(&key.as_str()) {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    scrub_value(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(scrub_value),
+        _ => {}
+    }
+}
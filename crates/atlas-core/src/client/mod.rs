@@ -0,0 +1,210 @@
+use std::{fmt, sync::Arc};
+
+use thiserror::Error;
+use url::Url;
+
+use crate::{AsUrl, AsUrlError, AtlasRequest};
+
+mod digest;
+mod fixture;
+mod token;
+mod transport;
+
+pub use digest::DigestChallengeError;
+/// Used by atlas-mock to verify digest credentials the way the client computes them.
+#[doc(hidden)]
+pub use digest::{md5_hex, split_params};
+pub use fixture::*;
+pub use token::*;
+pub use transport::*;
+
+pub const DEFAULT_BASE_URL: &str = "https://cloud.mongodb.com/";
+
+/// Credentials used to authenticate against the Atlas Admin API.
+#[derive(Clone)]
+pub enum Credentials {
+    /// Programmatic API keys, sent using HTTP digest authentication.
+    ApiKeys { public: String, private: String },
+    /// An OAuth access token, sent as a bearer token.
+    AccessToken(String),
+    /// OAuth access tokens obtained from a provider, sent as bearer tokens. A request
+    /// rejected with 401 is retried once with a refreshed token.
+    OAuth(Arc<dyn TokenProvider>),
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ApiKeys { public, .. } => f
+                .debug_struct("ApiKeys")
+                .field("public", public)
+                .finish_non_exhaustive(),
+            Self::AccessToken(_) => f.debug_tuple("AccessToken").finish_non_exhaustive(),
+            Self::OAuth(_) => f.debug_tuple("OAuth").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Atlas Admin API client.
+pub struct Client<T = HttpTransport> {
+    base_url: Url,
+    credentials: Option<Credentials>,
+    transport: T,
+}
+
+#[derive(Error, Debug)]
+pub enum SendError {
+    #[error("failed to build the request URL")]
+    Url(#[from] AsUrlError),
+    #[error("transport error")]
+    Transport(#[from] TransportError),
+    #[error("invalid digest challenge")]
+    DigestChallenge(#[from] DigestChallengeError),
+    #[error("failed to obtain an access token")]
+    Token(#[source] TokenError),
+}
+
+#[derive(Error, Debug)]
+pub enum ExecuteError {
+    #[error("failed to send the request")]
+    Send(#[from] SendError),
+    #[error("failed to serialize the request body")]
+    Serialize(#[source] serde_json::Error),
+    #[error("request failed with status {status}")]
+    Status { status: u16, body: String },
+    #[error("failed to deserialize the response body")]
+    Deserialize(#[source] serde_json::Error),
+}
+
+impl Client {
+    pub fn new(base_url: Url) -> Self {
+        Self::with_transport(base_url, HttpTransport::default())
+    }
+}
+
+impl<T: Transport> Client<T> {
+    pub fn with_transport(base_url: Url, transport: T) -> Self {
+        Self {
+            base_url,
+            credentials: None,
+            transport,
+        }
+    }
+
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Builds the URL for `request` against the base URL and sends it.
+    pub async fn send(
+        &self,
+        method: Method,
+        request: &impl AsUrl,
+        body: Option<String>,
+    ) -> Result<HttpResponse, SendError> {
+        let url = request.as_url(&self.base_url)?;
+
+        let mut http_request = HttpRequest::new(method, url);
+        http_request
+            .headers
+            .push(("Accept".to_string(), "application/json".to_string()));
+        if body.is_some() {
+            http_request
+                .headers
+                .push(("Content-Type".to_string(), "application/json".to_string()));
+        }
+        http_request.body = body;
+
+        self.send_http(http_request).await
+    }
+
+    /// Sends `request` with its method, versioned media type and body, and deserializes the
+    /// response. Responses without content, like `204 No Content`, deserialize from `null`.
+    pub async fn execute<R: AtlasRequest>(&self, request: &R) -> Result<R::Response, ExecuteError> {
+        let url = request.as_url(&self.base_url).map_err(SendError::from)?;
+        let media_type = R::media_type();
+
+        let mut http_request = HttpRequest::new(R::METHOD, url);
+        http_request
+            .headers
+            .push(("Accept".to_string(), media_type.clone()));
+        if let Some(body) = request.body() {
+            http_request
+                .headers
+                .push(("Content-Type".to_string(), media_type));
+            http_request.body = Some(serde_json::to_string(body).map_err(ExecuteError::Serialize)?);
+        }
+
+        let response = self.send_http(http_request).await?;
+        if !response.is_success() {
+            return Err(ExecuteError::Status {
+                status: response.status,
+                body: response.body,
+            });
+        }
+
+        let body = match response.body.trim() {
+            "" => "null",
+            body => body,
+        };
+        serde_json::from_str(body).map_err(ExecuteError::Deserialize)
+    }
+
+    /// Sends a prepared request, taking care of authentication.
+    pub async fn send_http(&self, mut request: HttpRequest) -> Result<HttpResponse, SendError> {
+        match &self.credentials {
+            None => Ok(self.transport.send(request).await?),
+            Some(Credentials::AccessToken(token)) => {
+                Ok(self.transport.send(with_bearer(request, token)).await?)
+            }
+            Some(Credentials::OAuth(provider)) => {
+                let token = provider.access_token().await.map_err(SendError::Token)?;
+                let response = self
+                    .transport
+                    .send(with_bearer(request.clone(), &token))
+                    .await?;
+                if response.status != 401 {
+                    return Ok(response);
+                }
+
+                let token = provider.refresh(&token).await.map_err(SendError::Token)?;
+                Ok(self.transport.send(with_bearer(request, &token)).await?)
+            }
+            Some(Credentials::ApiKeys { public, private }) => {
+                let response = self.transport.send(request.clone()).await?;
+                let challenge = match (response.status, response.header("www-authenticate")) {
+                    (401, Some(challenge)) => digest::DigestChallenge::parse(challenge)?,
+                    _ => return Ok(response),
+                };
+
+                let uri = match request.url.query() {
+                    Some(query) => format!("{}?{query}", request.url.path()),
+                    None => request.url.path().to_string(),
+                };
+                let authorization =
+                    challenge.authorization(public, private, request.method.as_str(), &uri);
+                request
+                    .headers
+                    .push(("Authorization".to_string(), authorization));
+
+                Ok(self.transport.send(request).await?)
+            }
+        }
+    }
+}
+
+fn with_bearer(mut request: HttpRequest, token: &str) -> HttpRequest {
+    request
+        .headers
+        .push(("Authorization".to_string(), format!("Bearer {token}")));
+    request
+}
@@ -0,0 +1,17 @@
+use std::{future::Future, pin::Pin};
+
+/// Error returned by a [`TokenProvider`].
+pub type TokenError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Future returned by a [`TokenProvider`], resolving to an access token.
+pub type TokenFuture<'a> = Pin<Box<dyn Future<Output = Result<String, TokenError>> + Send + 'a>>;
+
+/// Supplies OAuth access tokens which may change over time, e.g. because they are refreshed
+/// before they expire.
+pub trait TokenProvider: Send + Sync {
+    /// Returns a token which is expected to be accepted by the API.
+    fn access_token(&self) -> TokenFuture<'_>;
+
+    /// Called when the API rejected `rejected`, returns a token to retry the request with.
+    fn refresh<'a>(&'a self, rejected: &'a str) -> TokenFuture<'a>;
+}
@@ -1,5 +1,7 @@
-// Re-export the derive macro
-pub use atlas_derive::AtlasURL;
+// Re-export the derive macros
+pub use atlas_derive::{AtlasURL, MapArgs, MapEnum, TryFromMap};
 
 // Re-export everything from atlas-derive-core
 pub use atlas_derive_core::*;
+
+pub mod client;
@@ -0,0 +1,165 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use atlas_core::client::{
+    Client, Credentials, Fixture, FixtureError, FixtureMode, FixtureTransport, HttpRequest,
+    HttpResponse, Method, Transport, TransportError,
+};
+use atlas_core::AtlasURL;
+use url::Url;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters")]
+struct ListClusters {
+    group_id: String,
+    items_per_page: Option<u32>,
+    page_num: Option<u32>,
+}
+
+/// Pretends to be Atlas: challenges unauthenticated requests, then answers with a body
+/// containing a secret.
+#[derive(Default)]
+struct FakeAtlas {
+    calls: AtomicUsize,
+}
+
+impl Transport for FakeAtlas {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+
+        if request.header("authorization").is_none() {
+            return Ok(HttpResponse {
+                status: 401,
+                headers: vec![(
+                    "WWW-Authenticate".to_string(),
+                    r#"Digest realm="MMS Public API", nonce="n0nce", algorithm=MD5, qop="auth""#
+                        .to_string(),
+                )],
+                body: String::new(),
+            });
+        }
+
+        Ok(HttpResponse {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: format!(
+                r#"{{"results":[{{"name":"Cluster0","privateKey":"s3cr3t"}}],"path":"{}"}}"#,
+                request.url.path()
+            ),
+        })
+    }
+}
+
+/// Fails the test if a replaying transport ever reaches the network.
+struct Offline;
+
+impl Transport for Offline {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        panic!("unexpected network request to {}", request.url)
+    }
+}
+
+fn base_url() -> Url {
+    Url::parse("https://cloud.mongodb.com").unwrap()
+}
+
+fn credentials() -> Credentials {
+    Credentials::ApiKeys {
+        public: "public".to_string(),
+        private: "private".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn record_then_replay() {
+    let directory = tempfile::tempdir().unwrap();
+    let fixture_path = directory.path().join("fixtures/list_clusters.json");
+
+    let request = ListClusters {
+        group_id: "5f1".to_string(),
+        items_per_page: Some(10),
+        page_num: Some(2),
+    };
+
+    // Record
+    let transport =
+        FixtureTransport::new(FakeAtlas::default(), &fixture_path, FixtureMode::Record).unwrap();
+    let client = Client::with_transport(base_url(), transport).with_credentials(credentials());
+    let recorded = client.send(Method::Get, &request, None).await.unwrap();
+    assert_eq!(recorded.status, 200);
+    assert_eq!(client.transport().inner().calls.load(Ordering::SeqCst), 2);
+
+    let contents = std::fs::read_to_string(&fixture_path).unwrap();
+    assert!(!contents.contains("s3cr3t"));
+    assert!(!contents.contains("n0nce"));
+    assert!(!contents.contains("Digest username"));
+
+    let fixture: Fixture = serde_json::from_str(&contents).unwrap();
+    assert_eq!(fixture.interactions.len(), 1);
+    assert_eq!(
+        fixture.interactions[0].request.query,
+        vec![
+            ("items_per_page".to_string(), "10".to_string()),
+            ("page_num".to_string(), "2".to_string()),
+        ]
+    );
+
+    // Replay
+    let transport = FixtureTransport::new(Offline, &fixture_path, FixtureMode::Replay).unwrap();
+    let client = Client::with_transport(base_url(), transport).with_credentials(credentials());
+    let replayed = client.send(Method::Get, &request, None).await.unwrap();
+    assert_eq!(replayed.status, 200);
+    assert_eq!(
+        replayed.body,
+        r#"{"path":"/api/atlas/v2/groups/5f1/clusters","results":[{"name":"Cluster0","privateKey":"[REDACTED]"}]}"#
+    );
+
+    // Every recording is only replayed once
+    let error = client.send(Method::Get, &request, None).await.unwrap_err();
+    assert!(matches!(
+        error,
+        atlas_core::client::SendError::Transport(TransportError::Fixture(
+            FixtureError::NoMatch { .. }
+        ))
+    ));
+}
+
+#[tokio::test]
+async fn replay_ignores_query_order() {
+    let directory = tempfile::tempdir().unwrap();
+    let fixture_path = directory.path().join("fixture.json");
+
+    let transport =
+        FixtureTransport::new(FakeAtlas::default(), &fixture_path, FixtureMode::Record).unwrap();
+    let url = Url::parse("https://cloud.mongodb.com/api/atlas/v2/groups?b=2&a=1").unwrap();
+    let mut request = HttpRequest::new(Method::Get, url);
+    request
+        .headers
+        .push(("Authorization".to_string(), "Bearer token".to_string()));
+    transport.send(request).await.unwrap();
+
+    let transport = FixtureTransport::new(Offline, &fixture_path, FixtureMode::Replay).unwrap();
+    let url = Url::parse("https://cloud.mongodb.com/api/atlas/v2/groups?a=1&b=2").unwrap();
+    let response = transport
+        .send(HttpRequest::new(Method::Get, url))
+        .await
+        .unwrap();
+    assert_eq!(response.status, 200);
+
+    // A different method doesn't match
+    let url = Url::parse("https://cloud.mongodb.com/api/atlas/v2/groups?a=1&b=2").unwrap();
+    assert!(transport
+        .send(HttpRequest::new(Method::Delete, url))
+        .await
+        .is_err());
+}
+
+#[test]
+fn replay_requires_fixture_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let result = FixtureTransport::new(
+        Offline,
+        directory.path().join("missing.json"),
+        FixtureMode::Replay,
+    );
+    assert!(matches!(result, Err(FixtureError::Io { .. })));
+}
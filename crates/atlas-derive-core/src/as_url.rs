@@ -0,0 +1,181 @@
+use std::fmt::{self, Write};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use url::{form_urlencoded, Url};
+
+/// Everything except the characters RFC 3986 allows unencoded in a path segment: unreserved
+/// characters, sub-delimiters, `:` and `@`.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'!')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'*')
+    .remove(b'+')
+    .remove(b',')
+    .remove(b';')
+    .remove(b'=')
+    .remove(b':')
+    .remove(b'@');
+
+pub trait AsUrl {
+    /// Builds the URL of this request relative to `base_url`, keeping the path of the base
+    /// URL as a prefix, e.g. `https://host/prefix/` and `/api/v2/x` give
+    /// `https://host/prefix/api/v2/x`.
+    fn as_url(&self, base_url: impl IntoBaseUrl) -> Result<Url, AsUrlError>;
+}
+
+/// Types which can be used as the base URL of [`AsUrl::as_url`].
+pub trait IntoBaseUrl {
+    fn into_base_url(self) -> Result<Url, AsUrlError>;
+}
+
+impl IntoBaseUrl for Url {
+    fn into_base_url(self) -> Result<Url, AsUrlError> {
+        if self.cannot_be_a_base() {
+            return Err(AsUrlError::CannotBeABase(self.to_string()));
+        }
+        Ok(self)
+    }
+}
+
+impl IntoBaseUrl for &Url {
+    fn into_base_url(self) -> Result<Url, AsUrlError> {
+        self.clone().into_base_url()
+    }
+}
+
+impl IntoBaseUrl for &str {
+    fn into_base_url(self) -> Result<Url, AsUrlError> {
+        Url::parse(self)
+            .map_err(|source| AsUrlError::InvalidBaseUrl {
+                base_url: self.to_string(),
+                source,
+            })?
+            .into_base_url()
+    }
+}
+
+impl IntoBaseUrl for &String {
+    fn into_base_url(self) -> Result<Url, AsUrlError> {
+        self.as_str().into_base_url()
+    }
+}
+
+impl IntoBaseUrl for String {
+    fn into_base_url(self) -> Result<Url, AsUrlError> {
+        self.as_str().into_base_url()
+    }
+}
+
+#[derive(Debug)]
+pub enum AsUrlError {
+    InvalidBaseUrl {
+        base_url: String,
+        source: url::ParseError,
+    },
+    /// The base URL has no hierarchical path to append to, e.g. `mailto:`.
+    CannotBeABase(String),
+}
+
+impl fmt::Display for AsUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsUrlError::InvalidBaseUrl { base_url, .. } => {
+                write!(f, "Invalid base URL '{}'", base_url)
+            }
+            AsUrlError::CannotBeABase(base_url) => {
+                write!(f, "Base URL '{}' can't have a path", base_url)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AsUrlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AsUrlError::InvalidBaseUrl { source, .. } => Some(source),
+            AsUrlError::CannotBeABase(_) => None,
+        }
+    }
+}
+
+/// Percent-encodes a value so it ends up as a single path segment, e.g. `a/b` becomes `a%2Fb`.
+pub fn encode_path_segment(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT).to_string()
+}
+
+/// Percent-encodes everything written to it as part of a path segment.
+struct PathSegmentWriter<'a>(&'a mut String);
+
+impl Write for PathSegmentWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend(utf8_percent_encode(s, PATH_SEGMENT));
+        Ok(())
+    }
+}
+
+/// `application/x-www-form-urlencoded`-encodes everything written to it.
+struct QueryWriter<'a>(&'a mut String);
+
+impl Write for QueryWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend(form_urlencoded::byte_serialize(s.as_bytes()));
+        Ok(())
+    }
+}
+
+/// Appends `value` to `path` as a single path segment, percent-encoded like
+/// [`encode_path_segment`] but without formatting it into a `String` first.
+pub fn push_path_segment(path: &mut String, value: impl fmt::Display) {
+    // Writing into a `String` can't fail
+    let _ = write!(PathSegmentWriter(path), "{value}");
+}
+
+/// Appends `key=value` to the `application/x-www-form-urlencoded` `query`, encoded like
+/// [`url::form_urlencoded::Serializer::append_pair`].
+pub fn push_query_pair(query: &mut String, key: &str, value: impl fmt::Display) {
+    push_query_values(query, key, [value], "");
+}
+
+/// Appends `key=` and `values` joined with `separator` to the
+/// `application/x-www-form-urlencoded` `query`.
+pub fn push_query_values<T: fmt::Display>(
+    query: &mut String,
+    key: &str,
+    values: impl IntoIterator<Item = T>,
+    separator: &str,
+) {
+    if !query.is_empty() {
+        query.push('&');
+    }
+    let mut writer = QueryWriter(query);
+    let _ = writer.write_str(key);
+    writer.0.push('=');
+    for (i, value) in values.into_iter().enumerate() {
+        if i > 0 {
+            let _ = writer.write_str(separator);
+        }
+        let _ = write!(writer, "{value}");
+    }
+}
+
+/// Appends `path` to the path of `base_url` with exactly one slash in between, and drops the
+/// query and fragment of `base_url`.
+pub fn join_base_url(mut base_url: Url, path: &str) -> Url {
+    let joined = format!(
+        "{}/{}",
+        base_url.path().trim_end_matches('/'),
+        path.trim_start_matches('/')
+    );
+    base_url.set_path(&joined);
+    base_url.set_query(None);
+    base_url.set_fragment(None);
+    base_url
+}
@@ -0,0 +1,162 @@
+use serde_json::{json, Map, Value};
+
+use crate::Method;
+
+/// A static description of a URL: its path template, parameters and documentation.
+///
+/// Derived by `AtlasURL` when the struct has `#[url(describe)]`.
+pub trait DescribeUrl {
+    const DESCRIPTOR: UrlDescriptor;
+}
+
+/// Where a parameter goes in the URL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamLocation {
+    Path,
+    Query,
+}
+
+impl ParamLocation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParamLocation::Path => "path",
+            ParamLocation::Query => "query",
+        }
+    }
+}
+
+/// How the values of a `Vec` parameter are written, see `#[url(vec = "...")]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VecStyle {
+    /// `key=a&key=b`
+    Repeat,
+    /// `key=a,b`
+    Comma,
+    /// `key=a|b`
+    Pipe,
+    /// `key[]=a&key[]=b`
+    Brackets,
+}
+
+/// A path or query parameter of a [`UrlDescriptor`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParamDescriptor {
+    /// The placeholder or query key.
+    pub name: &'static str,
+    pub location: ParamLocation,
+    /// The Rust type of the values, e.g. `u32` for an `Option<Vec<u32>>` field.
+    pub type_name: &'static str,
+    /// Whether the parameter is always written: path parameters which aren't `#[url(optional)]`
+    /// and query parameters which are neither `Option`s nor `Vec`s.
+    pub required: bool,
+    /// How the values are written if the field is a `Vec`.
+    pub vec: Option<VecStyle>,
+    pub doc: Option<&'static str>,
+}
+
+/// The description of a URL generated by `#[url(describe)]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UrlDescriptor {
+    /// The name of the struct.
+    pub name: &'static str,
+    /// The path template, e.g. `/api/atlas/v2/groups/{groupId}`, with the placeholders named
+    /// like the parameters.
+    pub path: &'static str,
+    /// The method of an `AtlasRequest`.
+    pub method: Option<Method>,
+    pub doc: Option<&'static str>,
+    /// The path parameters in the order of the template, followed by the query parameters.
+    pub params: &'static [ParamDescriptor],
+}
+
+impl UrlDescriptor {
+    pub fn param(&self, name: &str) -> Option<&ParamDescriptor> {
+        self.params.iter().find(|param| param.name == name)
+    }
+
+    /// The OpenAPI 3 path item of the URL, to be found under [`UrlDescriptor::path`] in the
+    /// `paths` of a spec.
+    ///
+    /// With a method, it has a single operation whose id is the name of the struct in
+    /// camelCase, summarized by the first paragraph of the doc comment. Without one, the
+    /// parameters are those of the path item. Path parameters are always required in OpenAPI,
+    /// including the `#[url(optional)]` ones.
+    pub fn openapi_path_item(&self) -> Value {
+        let parameters: Vec<_> = self.params.iter().map(ParamDescriptor::openapi_parameter).collect();
+        let Some(method) = self.method else {
+            return json!({ "parameters": parameters });
+        };
+
+        let mut operation = Map::new();
+        operation.insert("operationId".to_string(), camel_case(self.name).into());
+        if let Some(doc) = self.doc {
+            let summary = doc.split("\n\n").next().unwrap_or(doc);
+            operation.insert("summary".to_string(), summary.into());
+            if summary != doc {
+                operation.insert("description".to_string(), doc.into());
+            }
+        }
+        operation.insert("parameters".to_string(), parameters.into());
+        json!({ method.as_str().to_ascii_lowercase(): operation })
+    }
+}
+
+impl ParamDescriptor {
+    /// The OpenAPI 3 parameter object of the parameter.
+    pub fn openapi_parameter(&self) -> Value {
+        // `brackets` values are written as `hosts[]=a&hosts[]=b`, so the key is `hosts[]`
+        let name = match (self.location, self.vec) {
+            (ParamLocation::Query, Some(VecStyle::Brackets)) if !self.name.ends_with("[]") => {
+                format!("{}[]", self.name)
+            }
+            _ => self.name.to_string(),
+        };
+        let mut parameter = Map::new();
+        parameter.insert("name".to_string(), name.into());
+        parameter.insert("in".to_string(), self.location.as_str().into());
+        parameter.insert(
+            "required".to_string(),
+            (self.required || self.location == ParamLocation::Path).into(),
+        );
+        if let Some(doc) = self.doc {
+            parameter.insert("description".to_string(), doc.into());
+        }
+
+        let schema = openapi_schema(self.type_name);
+        let Some(vec) = self.vec else {
+            parameter.insert("schema".to_string(), schema);
+            return parameter.into();
+        };
+        parameter.insert("schema".to_string(), json!({ "type": "array", "items": schema }));
+        let (style, explode) = match (self.location, vec) {
+            (ParamLocation::Path, _) => ("simple", false),
+            (ParamLocation::Query, VecStyle::Repeat | VecStyle::Brackets) => ("form", true),
+            (ParamLocation::Query, VecStyle::Comma) => ("form", false),
+            (ParamLocation::Query, VecStyle::Pipe) => ("pipeDelimited", false),
+        };
+        parameter.insert("style".to_string(), style.into());
+        parameter.insert("explode".to_string(), explode.into());
+        parameter.into()
+    }
+}
+
+/// The OpenAPI schema of a Rust type, a string unless it's a primitive.
+fn openapi_schema(type_name: &str) -> Value {
+    match type_name {
+        "bool" => json!({ "type": "boolean" }),
+        "i32" | "u16" | "i16" | "u8" | "i8" => json!({ "type": "integer", "format": "int32" }),
+        "i64" | "u32" | "u64" | "isize" | "usize" => json!({ "type": "integer", "format": "int64" }),
+        "f32" => json!({ "type": "number", "format": "float" }),
+        "f64" => json!({ "type": "number", "format": "double" }),
+        _ => json!({ "type": "string" }),
+    }
+}
+
+/// Lowercases the first letter of a PascalCase name.
+fn camel_case(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
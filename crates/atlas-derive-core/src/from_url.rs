@@ -0,0 +1,141 @@
+use std::{borrow::Cow, fmt};
+
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+use crate::{TryFromMapError, TryFromMapErrors};
+
+pub trait FromUrl: Sized {
+    /// Parses a URL built by [`AsUrl::as_url`](crate::AsUrl::as_url), e.g. a `links[].href`
+    /// of a paged response. The path of the URL has to end with the URL pattern, whatever
+    /// comes before it is taken to be the path of the base URL.
+    fn from_url(url: &Url) -> Result<Self, FromUrlError>;
+}
+
+/// A piece of a URL pattern, see [`match_path`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternPart {
+    Literal(&'static str),
+    Placeholder,
+    /// A `/` followed by a placeholder, or nothing.
+    OptionalSegment,
+}
+
+/// Matches the percent-encoded `path` against `pattern`, allowing any prefix starting with a
+/// `/` before it. Returns the values of the placeholders, which never contain a `/` and are
+/// only `None` for omitted optional segments.
+pub fn match_path<'a>(path: &'a str, pattern: &[PatternPart]) -> Option<Vec<Option<&'a str>>> {
+    path.match_indices('/').find_map(|(start, _)| {
+        let mut values = Vec::new();
+        match_parts(&path[start..], pattern, &mut values).then_some(values)
+    })
+}
+
+fn match_parts<'a>(
+    path: &'a str,
+    pattern: &[PatternPart],
+    values: &mut Vec<Option<&'a str>>,
+) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((PatternPart::Literal(literal), rest)) => path
+            .strip_prefix(literal)
+            .is_some_and(|path| match_parts(path, rest, values)),
+        Some((PatternPart::Placeholder, rest)) => match_placeholder(path, rest, values),
+        Some((PatternPart::OptionalSegment, rest)) => {
+            path.strip_prefix('/')
+                .is_some_and(|path| match_placeholder(path, rest, values))
+                || matches(values, None, |values| match_parts(path, rest, values))
+        }
+    }
+}
+
+fn match_placeholder<'a>(
+    path: &'a str,
+    rest: &[PatternPart],
+    values: &mut Vec<Option<&'a str>>,
+) -> bool {
+    // Placeholders are matched greedily, backtracking when the rest doesn't match
+    let end = path.find('/').unwrap_or(path.len());
+    (0..=end)
+        .rev()
+        .filter(|&len| path.is_char_boundary(len))
+        .any(|len| {
+            matches(values, Some(&path[..len]), |values| {
+                match_parts(&path[len..], rest, values)
+            })
+        })
+}
+
+/// Pushes `value` while trying `f`, keeping it only if `f` matched.
+fn matches<'a>(
+    values: &mut Vec<Option<&'a str>>,
+    value: Option<&'a str>,
+    f: impl FnOnce(&mut Vec<Option<&'a str>>) -> bool,
+) -> bool {
+    values.push(value);
+    let matched = f(values);
+    if !matched {
+        values.pop();
+    }
+    matched
+}
+
+/// Decodes a path segment encoded by [`encode_path_segment`](crate::encode_path_segment).
+/// Returns `None` if it doesn't decode to UTF-8.
+pub fn decode_path_segment(segment: &str) -> Option<Cow<'_, str>> {
+    percent_decode_str(segment).decode_utf8().ok()
+}
+
+#[derive(Debug)]
+pub enum FromUrlError {
+    /// The path of the URL doesn't end with the URL pattern.
+    PathMismatch { pattern: &'static str, path: String },
+    /// A path parameter can't be decoded or parsed.
+    InvalidPathParam {
+        field: String,
+        value: String,
+        /// The error returned by `FromStr`, `None` if the value isn't UTF-8 once decoded.
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+    /// The query doesn't match the query parameters.
+    Query(TryFromMapErrors),
+}
+
+impl fmt::Display for FromUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromUrlError::PathMismatch { pattern, path } => {
+                write!(f, "Path '{}' doesn't match '{}'", path, pattern)
+            }
+            FromUrlError::InvalidPathParam { field, value, .. } => {
+                write!(f, "Failed to parse path parameter '{}' with value '{}'", field, value)
+            }
+            FromUrlError::Query(_) => write!(f, "Invalid query"),
+        }
+    }
+}
+
+impl std::error::Error for FromUrlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FromUrlError::InvalidPathParam { source, .. } => {
+                source.as_deref().map(|source| source as &(dyn std::error::Error + 'static))
+            }
+            FromUrlError::Query(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<TryFromMapErrors> for FromUrlError {
+    fn from(errors: TryFromMapErrors) -> Self {
+        FromUrlError::Query(errors)
+    }
+}
+
+impl From<TryFromMapError> for FromUrlError {
+    fn from(error: TryFromMapError) -> Self {
+        FromUrlError::Query(error.into())
+    }
+}
@@ -1,10 +1,65 @@
-use url::Url;
+mod as_url;
+mod describe;
+mod from_url;
+#[cfg(feature = "clap")]
+mod map_args;
+mod map_value;
+mod request;
+mod try_from_map;
+
+pub use as_url::*;
+pub use describe::*;
+pub use from_url::*;
+#[cfg(feature = "clap")]
+pub use map_args::*;
+pub use map_value::*;
+pub use request::*;
+pub use try_from_map::*;
+
+/// The `url` crate, whose `Url` is used by `AsUrl` and `FromUrl`.
+pub use url;
+
+/// The `clap` crate, whose `Arg`s are built by `MapArgs`.
+#[cfg(feature = "clap")]
+pub use clap;
+
+/// Used by the derived code.
+#[doc(hidden)]
+pub mod __private {
+    use std::borrow::Cow;
+
+    pub use serde::{de::DeserializeOwned, Serialize};
+
+    /// The key of a field read with `prefix`, only allocating for nested fields.
+    pub fn prefixed<'a>(prefix: &str, key: &'a str) -> Cow<'a, str> {
+        if prefix.is_empty() {
+            Cow::Borrowed(key)
+        } else {
+            Cow::Owned(format!("{}{}", prefix, key))
+        }
+    }
+
+    /// The long flag of a field read with `prefix`, e.g. `--filter-name` for `filter.`.
+    pub fn prefixed_long(prefix: &str, long: &str) -> String {
+        let prefix = prefix.replace(|c: char| !c.is_alphanumeric(), "-");
+        format!("{}{}", prefix, long)
+    }
+}
 
 #[derive(Debug)]
 pub enum TryFromMapError {
     MissingField(String),
     NoValuesInField(String),
-    ParseError { field: String, value: String },
+    /// A key which isn't read by any field, with `#[map(deny_unknown_fields)]`.
+    UnknownField(String),
+    /// Several values for a field with `#[map(single)]`.
+    TooManyValues(String),
+    ParseError {
+        field: String,
+        value: String,
+        /// The error returned by `FromStr` or by the `parse_with` function.
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
 }
 
 impl std::fmt::Display for TryFromMapError {
@@ -12,15 +67,85 @@ impl std::fmt::Display for TryFromMapError {
         match self {
             TryFromMapError::MissingField(field) => write!(f, "Missing field: {}", field),
             TryFromMapError::NoValuesInField(field) => write!(f, "Field '{}' exists but contains no values", field),
-            TryFromMapError::ParseError { field, value } => {
+            TryFromMapError::UnknownField(field) => write!(f, "Unknown field: {}", field),
+            TryFromMapError::TooManyValues(field) => write!(f, "Field '{}' has more than one value", field),
+            TryFromMapError::ParseError { field, value, .. } => {
                 write!(f, "Failed to parse field '{}' with value '{}'", field, value)
             }
         }
     }
 }
 
-impl std::error::Error for TryFromMapError {}
+impl std::error::Error for TryFromMapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TryFromMapError::ParseError { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// The errors of all the fields which couldn't be read by `TryFromMap`, in the order of the
+/// fields, so they can be reported at once.
+#[derive(Debug)]
+pub struct TryFromMapErrors(Vec<TryFromMapError>);
+
+impl TryFromMapErrors {
+    pub fn errors(&self) -> &[TryFromMapError] {
+        &self.0
+    }
+
+    pub fn into_errors(self) -> Vec<TryFromMapError> {
+        self.0
+    }
+}
+
+impl From<Vec<TryFromMapError>> for TryFromMapErrors {
+    fn from(errors: Vec<TryFromMapError>) -> Self {
+        TryFromMapErrors(errors)
+    }
+}
 
-pub trait AsUrl {
-    fn as_url(&self, base_url: &str) -> Result<Url, url::ParseError>;
+impl From<TryFromMapError> for TryFromMapErrors {
+    fn from(error: TryFromMapError) -> Self {
+        TryFromMapErrors(vec![error])
+    }
 }
+
+impl IntoIterator for TryFromMapErrors {
+    type Item = TryFromMapError;
+    type IntoIter = std::vec::IntoIter<TryFromMapError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a TryFromMapErrors {
+    type Item = &'a TryFromMapError;
+    type IntoIter = std::slice::Iter<'a, TryFromMapError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl std::fmt::Display for TryFromMapErrors {
+    /// Writes each error followed by its sources, as there's no single source to chain to.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", error)?;
+            let mut source = std::error::Error::source(error);
+            while let Some(error) = source {
+                write!(f, ": {}", error)?;
+                source = error.source();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TryFromMapErrors {}
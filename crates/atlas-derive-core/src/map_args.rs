@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use clap::{Arg, ArgMatches, Command};
+
+use crate::{TryFromMap, TryFromMapErrors};
+
+/// A `TryFromMap` type read from command-line arguments, one per field, whose ids are the keys
+/// of the fields. The values of the arguments are parsed and validated like any other map, so
+/// the same struct can be read from flags and turned into a URL.
+pub trait MapArgs: TryFromMap {
+    /// The arguments of the fields, whose ids are the keys of the fields read with `prefix`.
+    fn args(prefix: &str) -> Vec<Arg>;
+
+    /// Adds the arguments of the fields to `command`.
+    fn augment_command(command: Command) -> Command {
+        command.args(Self::args(""))
+    }
+
+    /// Builds the type from the values of its arguments in `matches`, which may contain other
+    /// arguments as well.
+    fn from_arg_matches(matches: &ArgMatches) -> Result<Self, TryFromMapErrors> {
+        let mut map = HashMap::new();
+        for arg in Self::args("") {
+            let id = arg.get_id().as_str();
+            if let Ok(Some(values)) = matches.try_get_raw(id) {
+                let values = values.map(|v| v.to_string_lossy().into_owned()).collect::<Vec<_>>();
+                map.insert(id.to_string(), values);
+            }
+        }
+        Self::try_from_map(&map)
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+const TRUE: &[&str] = &["true", "1", "yes", "on", ""];
+const FALSE: &[&str] = &["false", "0", "no", "off"];
+
+/// Parses the value of a `bool` field of `TryFromMap`, in any case: `true`, `1`, `yes` and `on`,
+/// or `false`, `0`, `no` and `off`. An empty value, e.g. of a key given without one like in
+/// `?verbose`, is `true`.
+pub fn parse_flag(value: &str) -> Result<bool, ParseFlagError> {
+    if TRUE.iter().any(|flag| value.eq_ignore_ascii_case(flag)) {
+        Ok(true)
+    } else if FALSE.iter().any(|flag| value.eq_ignore_ascii_case(flag)) {
+        Ok(false)
+    } else {
+        Err(ParseFlagError {
+            value: value.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFlagError {
+    value: String,
+}
+
+impl fmt::Display for ParseFlagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid flag '{}', expected one of true, false, 1, 0, yes, no, on, off",
+            self.value
+        )
+    }
+}
+
+impl std::error::Error for ParseFlagError {}
+
+/// The error of the `FromStr` impl of `#[derive(MapEnum)]`, for a value which isn't the name
+/// of a variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseEnumError {
+    value: String,
+    expected: &'static [&'static str],
+}
+
+impl ParseEnumError {
+    pub fn new(value: &str, expected: &'static [&'static str]) -> Self {
+        ParseEnumError {
+            value: value.to_string(),
+            expected,
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// The names of the variants.
+    pub fn expected(&self) -> &'static [&'static str] {
+        self.expected
+    }
+}
+
+impl fmt::Display for ParseEnumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown variant '{}', expected one of {}", self.value, self.expected.join(", "))
+    }
+}
+
+impl std::error::Error for ParseEnumError {}
@@ -0,0 +1,55 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::AsUrl;
+
+/// HTTP methods used by the Atlas Admin API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Patch => "PATCH",
+            Method::Delete => "DELETE",
+        }
+    }
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An Atlas Admin API operation: where it lives, how it is called and what it returns.
+///
+/// Derived by `AtlasURL` when the struct has `#[url(method = "...")]`.
+pub trait AtlasRequest: AsUrl {
+    const METHOD: Method;
+    /// The API version, e.g. `2023-02-01`, sent as `application/vnd.atlas.{VERSION}+json`.
+    const VERSION: Option<&'static str>;
+
+    type Body: Serialize;
+    type Response: DeserializeOwned;
+
+    /// The request body, taken from the field marked with `#[url(body)]`.
+    fn body(&self) -> Option<&Self::Body>;
+
+    /// The media type of the request and response bodies.
+    fn media_type() -> String {
+        match Self::VERSION {
+            Some(version) => format!("application/vnd.atlas.{version}+json"),
+            None => "application/json".to_string(),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    hash::{BuildHasher, Hash},
+};
+
+use url::{form_urlencoded, Url};
+
+use crate::{TryFromMapError, TryFromMapErrors};
+
+/// Types built from the values of each key of e.g. query parameters or form data, implemented
+/// by `#[derive(TryFromMap)]`.
+pub trait TryFromMap: Sized {
+    /// Keys which aren't read by any field are an error, with `#[map(deny_unknown_fields)]`.
+    /// Only the type the map is read into checks this, not the nested ones.
+    const DENY_UNKNOWN_FIELDS: bool = false;
+
+    /// Reads the fields from the keys starting with `prefix`, adding their errors to `errors`.
+    /// Returns `None` if any of them failed.
+    ///
+    /// The type parameters are named so that they don't clash with those of derived types.
+    fn read_fields<__K, __V, __S>(
+        map: &HashMap<__K, Vec<__V>, __S>,
+        prefix: &str,
+        errors: &mut Vec<TryFromMapError>,
+    ) -> Option<Self>
+    where
+        __K: Borrow<str> + Hash + Eq,
+        __V: AsRef<str>,
+        __S: BuildHasher;
+
+    /// Checks if `key` is read by one of the fields, including those of nested types.
+    fn has_field(key: &str) -> bool;
+
+    /// Builds the value from the values of each key, e.g. an owned
+    /// `HashMap<String, Vec<String>>` or a borrowed `HashMap<&str, Vec<&str>>`.
+    fn try_from_map<K, V, S>(map: &HashMap<K, Vec<V>, S>) -> Result<Self, TryFromMapErrors>
+    where
+        K: Borrow<str> + Hash + Eq,
+        V: AsRef<str>,
+        S: BuildHasher,
+    {
+        let mut errors = Vec::new();
+        let value = Self::read_fields(map, "", &mut errors);
+        if Self::DENY_UNKNOWN_FIELDS {
+            let mut unknown_fields: Vec<&str> = map
+                .keys()
+                .map(Borrow::borrow)
+                .filter(|key| !Self::has_field(key))
+                .collect();
+            // Sorted as the order of the keys of a `HashMap` is arbitrary
+            unknown_fields.sort_unstable();
+            errors.extend(
+                unknown_fields
+                    .into_iter()
+                    .map(|key| TryFromMapError::UnknownField(key.to_string())),
+            );
+        }
+        match value {
+            Some(value) if errors.is_empty() => Ok(value),
+            _ => Err(errors.into()),
+        }
+    }
+
+    /// Builds the value from `(key, value)` pairs, in which keys can be repeated.
+    fn try_from_pairs<K, V>(pairs: impl IntoIterator<Item = (K, V)>) -> Result<Self, TryFromMapErrors>
+    where
+        K: Borrow<str> + Hash + Eq,
+        V: AsRef<str>,
+    {
+        let mut map: HashMap<K, Vec<V>> = HashMap::new();
+        for (key, value) in pairs {
+            map.entry(key).or_default().push(value);
+        }
+        Self::try_from_map(&map)
+    }
+
+    /// Builds the value from a percent-encoded query string, with or without its leading `?`.
+    /// `+` is decoded as a space, as in `application/x-www-form-urlencoded` data.
+    fn try_from_query(query: &str) -> Result<Self, TryFromMapErrors> {
+        let query = query.strip_prefix('?').unwrap_or(query);
+        Self::try_from_pairs(form_urlencoded::parse(query.as_bytes()))
+    }
+
+    /// Builds the value from the query of `url`.
+    fn try_from_url(url: &Url) -> Result<Self, TryFromMapErrors> {
+        Self::try_from_pairs(url.query_pairs())
+    }
+}
+
+/// Types written into the values of each key, the reverse of [`TryFromMap`], implemented by
+/// `#[derive(TryFromMap)]` with `#[map(to_map)]` along with
+/// `From<&T> for HashMap<String, Vec<String>>`.
+pub trait ToMap {
+    /// Adds the values of the fields to `map`, under their keys prefixed with `prefix`.
+    fn write_fields(&self, prefix: &str, map: &mut HashMap<String, Vec<String>>);
+
+    /// The values of each key, which [`TryFromMap::try_from_map`] reads back.
+    fn to_map(&self) -> HashMap<String, Vec<String>> {
+        let mut map = HashMap::new();
+        self.write_fields("", &mut map);
+        map
+    }
+}
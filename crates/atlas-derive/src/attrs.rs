@@ -0,0 +1,487 @@
+use syn::{
+    ext::IdentExt, parse::ParseStream, spanned::Spanned, Attribute, Expr, ExprLit, Field, Lit,
+    LitStr, Token,
+};
+
+use crate::{
+    case::RenameRule,
+    url::{get_inner_type, is_option_type, is_vec_type},
+};
+
+/// Attributes on the struct: `#[url("/path/{param}", rename_all = "camelCase")]`, and
+/// `method = "GET"`, `version = "2023-02-01"` and `response = Type` describing the request, and
+/// `describe` to implement `DescribeUrl`.
+///
+/// The pattern and the options may also be split over several `#[url(...)]` attributes.
+#[derive(Default)]
+pub(crate) struct ContainerAttrs {
+    pub(crate) pattern: Option<LitStr>,
+    pub(crate) rename_all: Option<RenameRule>,
+    pub(crate) method: Option<syn::Ident>,
+    pub(crate) version: Option<LitStr>,
+    pub(crate) response: Option<syn::Type>,
+    pub(crate) describe: bool,
+}
+
+const METHODS: &[(&str, &str)] = &[
+    ("GET", "Get"),
+    ("POST", "Post"),
+    ("PUT", "Put"),
+    ("PATCH", "Patch"),
+    ("DELETE", "Delete"),
+];
+
+/// Parses an HTTP method like `"GET"` into the name of the `Method` variant.
+fn parse_method(lit: &LitStr) -> syn::Result<syn::Ident> {
+    let value = lit.value();
+    METHODS
+        .iter()
+        .find(|(method, _)| *method == value)
+        .map(|(_, variant)| syn::Ident::new(variant, lit.span()))
+        .ok_or_else(|| {
+            syn::Error::new(
+                lit.span(),
+                format!("unknown method `{value}`, expected one of `GET`, `POST`, `PUT`, `PATCH`, `DELETE`"),
+            )
+        })
+}
+
+/// Checks that an API version looks like a date, e.g. `2023-02-01`.
+fn parse_version(lit: &LitStr) -> syn::Result<LitStr> {
+    let value = lit.value();
+    let is_date = value.len() == 10
+        && value.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        });
+    if !is_date {
+        return Err(syn::Error::new(
+            lit.span(),
+            format!("invalid API version `{value}`, expected a date like `2023-02-01`"),
+        ));
+    }
+    Ok(lit.clone())
+}
+
+impl ContainerAttrs {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container = Self::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("url")) {
+            attr.parse_args_with(|input: ParseStream| {
+                while !input.is_empty() {
+                    if input.peek(LitStr) {
+                        let pattern: LitStr = input.parse()?;
+                        if container.pattern.is_some() {
+                            return Err(syn::Error::new(
+                                pattern.span(),
+                                "duplicate URL pattern",
+                            ));
+                        }
+                        container.pattern = Some(pattern);
+                    } else {
+                        let key = input.call(syn::Ident::parse_any)?;
+                        let duplicate = if key == "describe" {
+                            std::mem::replace(&mut container.describe, true)
+                        } else {
+                            input.parse::<Token![=]>()?;
+                            match key.to_string().as_str() {
+                                "rename_all" => container
+                                    .rename_all
+                                    .replace(RenameRule::from_lit(&input.parse()?)?)
+                                    .is_some(),
+                                "method" => container
+                                    .method
+                                    .replace(parse_method(&input.parse()?)?)
+                                    .is_some(),
+                                "version" => container
+                                    .version
+                                    .replace(parse_version(&input.parse()?)?)
+                                    .is_some(),
+                                "response" => container.response.replace(input.parse()?).is_some(),
+                                _ => {
+                                    return Err(syn::Error::new(
+                                        key.span(),
+                                        format!(
+                                            "unknown url attribute `{key}`, expected a URL pattern, `rename_all`, `method`, `version`, `response` or `describe`"
+                                        ),
+                                    ))
+                                }
+                            }
+                        };
+                        if duplicate {
+                            return Err(syn::Error::new(key.span(), format!("duplicate `{key}`")));
+                        }
+                    }
+
+                    if !input.is_empty() {
+                        input.parse::<Token![,]>()?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(container)
+    }
+}
+
+/// How a `Vec` is encoded in a URL, selected with `vec = "..."`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum VecStyle {
+    /// `key=a&key=b`
+    Repeat,
+    /// `key=a,b`
+    Comma,
+    /// `key=a|b`
+    Pipe,
+    /// `key[]=a&key[]=b`
+    Brackets,
+}
+
+impl VecStyle {
+    fn from_lit(lit: &LitStr) -> syn::Result<Self> {
+        match lit.value().as_str() {
+            "repeat" => Ok(Self::Repeat),
+            "comma" => Ok(Self::Comma),
+            "pipe" => Ok(Self::Pipe),
+            "brackets" => Ok(Self::Brackets),
+            value => Err(syn::Error::new(
+                lit.span(),
+                format!(
+                    "unknown vec style `{value}`, expected one of `repeat`, `comma`, `pipe`, `brackets`"
+                ),
+            )),
+        }
+    }
+
+    /// The separator of the values joined into a single value.
+    pub(crate) fn separator(self) -> Option<&'static str> {
+        match self {
+            Self::Comma => Some(","),
+            Self::Pipe => Some("|"),
+            Self::Repeat | Self::Brackets => None,
+        }
+    }
+
+    /// The key a value is stored under.
+    pub(crate) fn key(self, name: &str) -> String {
+        match self {
+            Self::Brackets => format!("{name}[]"),
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// Where a field ends up in the URL.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Placement {
+    /// In the path if the pattern has a placeholder for it, in the query otherwise.
+    #[default]
+    Auto,
+    Path,
+    Query,
+}
+
+/// Attributes on a field: `#[url(rename = "itemsPerPage")]`, `#[url(skip)]`,
+/// `#[url(path)]`, `#[url(query)]`, `#[url(raw)]`, `#[url(vec = "comma")]`,
+/// `#[url(optional)]` and `#[url(body)]`.
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    pub(crate) rename: Option<String>,
+    pub(crate) skip: bool,
+    pub(crate) placement: Placement,
+    /// The value is already percent-encoded and is spliced into the path as is.
+    pub(crate) raw: bool,
+    pub(crate) vec: Option<VecStyle>,
+    /// The `Option` path parameter's segment is left out of the path when it's `None`.
+    pub(crate) optional: bool,
+    /// The field is the request body rather than part of the URL.
+    pub(crate) body: bool,
+}
+
+impl FieldAttrs {
+    pub(crate) fn parse(field: &Field) -> syn::Result<Self> {
+        let mut attrs = Self::default();
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("url")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let rename: LitStr = meta.value()?.parse()?;
+                    if attrs.rename.replace(rename.value()).is_some() {
+                        return Err(meta.error("duplicate `rename`"));
+                    }
+                } else if meta.path.is_ident("skip") {
+                    attrs.skip = true;
+                } else if meta.path.is_ident("path") || meta.path.is_ident("query") {
+                    let placement = if meta.path.is_ident("path") {
+                        Placement::Path
+                    } else {
+                        Placement::Query
+                    };
+                    if attrs.placement != Placement::Auto && attrs.placement != placement {
+                        return Err(meta.error("a field can't be both `path` and `query`"));
+                    }
+                    attrs.placement = placement;
+                } else if meta.path.is_ident("raw") {
+                    attrs.raw = true;
+                } else if meta.path.is_ident("vec") {
+                    attrs.vec = Some(parse_vec_style(&meta, field)?);
+                } else if meta.path.is_ident("optional") {
+                    if !is_option_type(&field.ty) {
+                        return Err(meta.error("`optional` is only supported on `Option` fields"));
+                    }
+                    attrs.optional = true;
+                } else if meta.path.is_ident("body") {
+                    attrs.body = true;
+                } else {
+                    return Err(meta.error(
+                        "unknown url attribute, expected one of `rename`, `skip`, `path`, `query`, `raw`, `vec`, `optional`, `body`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+
+        if (attrs.skip || attrs.body) && attrs.placement != Placement::Auto {
+            return Err(syn::Error::new(
+                field.span(),
+                "a skipped or body field can't be placed in the `path` or `query`",
+            ));
+        }
+
+        Ok(attrs)
+    }
+
+    /// The name of the field in the URL: the explicit rename, the field name converted by
+    /// `rename_all`, or the field name.
+    pub(crate) fn effective_name(&self, field: &Field, rename_all: Option<RenameRule>) -> String {
+        effective_name(self.rename.as_deref(), field, rename_all)
+    }
+}
+
+fn effective_name(rename: Option<&str>, field: &Field, rename_all: Option<RenameRule>) -> String {
+    if let Some(rename) = rename {
+        return rename.to_string();
+    }
+
+    let name = field
+        .ident
+        .as_ref()
+        .expect("named field")
+        .unraw()
+        .to_string();
+    match rename_all {
+        Some(rule) => rule.apply(&name),
+        None => name,
+    }
+}
+
+/// Checks if the field is a `Vec` or an `Option<Vec>`.
+pub(crate) fn is_vec_field(field: &Field) -> bool {
+    let ty = match get_inner_type(&field.ty) {
+        Some(inner) if is_option_type(&field.ty) => inner,
+        _ => &field.ty,
+    };
+    is_vec_type(ty)
+}
+
+/// Parses `vec = "..."`, which is only allowed on `Vec` and `Option<Vec>` fields.
+fn parse_vec_style(meta: &syn::meta::ParseNestedMeta, field: &Field) -> syn::Result<VecStyle> {
+    let style = VecStyle::from_lit(&meta.value()?.parse()?)?;
+    if !is_vec_field(field) {
+        return Err(meta.error("`vec` is only supported on `Vec` fields"));
+    }
+    Ok(style)
+}
+
+/// Attributes on a `TryFromMap` struct: `#[map(rename_all = "camelCase")]`, and
+/// `#[map(deny_unknown_fields)]` and `#[map(single)]` making it strict about its input.
+#[derive(Default)]
+pub(crate) struct MapContainerAttrs {
+    pub(crate) rename_all: Option<RenameRule>,
+    /// Keys which aren't read by any field are an error.
+    pub(crate) deny_unknown_fields: bool,
+    /// Several values for a field which isn't a `Vec` are an error, for all the fields.
+    pub(crate) single: bool,
+    /// Also implement the reverse conversion, `ToMap`.
+    pub(crate) to_map: bool,
+}
+
+impl MapContainerAttrs {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container = Self::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("map")) {
+            attr.parse_nested_meta(|meta| {
+                let duplicate = if meta.path.is_ident("rename_all") {
+                    let rule = RenameRule::from_lit(&meta.value()?.parse()?)?;
+                    container.rename_all.replace(rule).is_some()
+                } else if meta.path.is_ident("deny_unknown_fields") {
+                    std::mem::replace(&mut container.deny_unknown_fields, true)
+                } else if meta.path.is_ident("single") {
+                    std::mem::replace(&mut container.single, true)
+                } else if meta.path.is_ident("to_map") {
+                    std::mem::replace(&mut container.to_map, true)
+                } else {
+                    return Err(meta.error(
+                        "unknown map attribute, expected one of `rename_all`, `deny_unknown_fields`, `single`, `to_map`",
+                    ));
+                };
+                if duplicate {
+                    let name = meta.path.get_ident().expect("known attribute");
+                    return Err(meta.error(format!("duplicate `{}`", name)));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(container)
+    }
+}
+
+/// The value of a field missing from the map, set with `default` or `default = "path"`.
+pub(crate) enum FieldDefault {
+    /// `Default::default()`
+    Trait,
+    /// A function returning the value.
+    Path(syn::ExprPath),
+}
+
+/// Attributes on a `TryFromMap` field: `#[map(rename = "pageNum")]`, `#[map(vec = "comma")]`,
+/// `#[map(default)]`, `#[map(default = "path")]`, `#[map(parse_with = "path")]` and
+/// `#[map(single)]`, or `#[map(flatten)]` and `#[map(prefix = "filter.")]` for fields which
+/// are themselves read from the map.
+#[derive(Default)]
+pub(crate) struct MapFieldAttrs {
+    pub(crate) rename: Option<String>,
+    pub(crate) vec: Option<VecStyle>,
+    pub(crate) default: Option<FieldDefault>,
+    /// A `fn(&str) -> Result<T, E>` parsing the values instead of `FromStr`.
+    pub(crate) parse_with: Option<syn::ExprPath>,
+    /// Several values are an error instead of taking the first one.
+    pub(crate) single: bool,
+    /// The field is read from the same keys as the struct.
+    pub(crate) flatten: bool,
+    /// The field is read from the keys starting with the prefix, without it.
+    pub(crate) prefix: Option<String>,
+}
+
+impl MapFieldAttrs {
+    pub(crate) fn parse(field: &Field) -> syn::Result<Self> {
+        let mut attrs = Self::default();
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("map")) {
+            attr.parse_nested_meta(|meta| {
+                let duplicate = if meta.path.is_ident("rename") {
+                    let rename: LitStr = meta.value()?.parse()?;
+                    attrs.rename.replace(rename.value()).is_some()
+                } else if meta.path.is_ident("vec") {
+                    attrs.vec.replace(parse_vec_style(&meta, field)?).is_some()
+                } else if meta.path.is_ident("default") {
+                    let default = if meta.input.peek(Token![=]) {
+                        let path: LitStr = meta.value()?.parse()?;
+                        FieldDefault::Path(path.parse()?)
+                    } else {
+                        FieldDefault::Trait
+                    };
+                    attrs.default.replace(default).is_some()
+                } else if meta.path.is_ident("parse_with") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    attrs.parse_with.replace(path.parse()?).is_some()
+                } else if meta.path.is_ident("single") {
+                    if is_vec_field(field) {
+                        return Err(meta.error("`single` isn't supported on `Vec` fields"));
+                    }
+                    std::mem::replace(&mut attrs.single, true)
+                } else if meta.path.is_ident("flatten") {
+                    std::mem::replace(&mut attrs.flatten, true)
+                } else if meta.path.is_ident("prefix") {
+                    let prefix: LitStr = meta.value()?.parse()?;
+                    attrs.prefix.replace(prefix.value()).is_some()
+                } else {
+                    return Err(meta.error(
+                        "unknown map attribute, expected one of `rename`, `vec`, `default`, `parse_with`, `single`, `flatten`, `prefix`",
+                    ));
+                };
+                if duplicate {
+                    return Err(meta.error("duplicate map attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        let parsed = attrs.rename.is_some()
+            || attrs.vec.is_some()
+            || attrs.default.is_some()
+            || attrs.parse_with.is_some()
+            || attrs.single;
+        if attrs.is_nested() && parsed {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`flatten` and `prefix` can't be combined with `rename`, `vec`, `default`, `parse_with` or `single`",
+            ));
+        }
+
+        Ok(attrs)
+    }
+
+    /// Checks if the field is read with its own `TryFromMap` impl, with `flatten` or `prefix`.
+    pub(crate) fn is_nested(&self) -> bool {
+        self.flatten || self.prefix.is_some()
+    }
+
+    /// The key of the field in the map: the explicit rename, the field name converted by
+    /// `rename_all`, or the field name.
+    pub(crate) fn effective_name(&self, field: &Field, rename_all: Option<RenameRule>) -> String {
+        effective_name(self.rename.as_deref(), field, rename_all)
+    }
+}
+
+/// Attributes on a `MapEnum` variant: `#[map(rename = "M10")]`.
+#[derive(Default)]
+pub(crate) struct MapVariantAttrs {
+    pub(crate) rename: Option<String>,
+}
+
+impl MapVariantAttrs {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut variant = Self::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("map")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let rename: LitStr = meta.value()?.parse()?;
+                    if variant.rename.replace(rename.value()).is_some() {
+                        return Err(meta.error("duplicate `rename`"));
+                    }
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown map attribute, expected `rename`"))
+                }
+            })?;
+        }
+
+        Ok(variant)
+    }
+}
+
+/// The doc comment in `attrs`, with the lines of each paragraph joined by spaces and the
+/// paragraphs separated by blank lines.
+pub(crate) fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta.require_name_value().ok()?.value {
+            Expr::Lit(ExprLit { lit: Lit::Str(doc), .. }) => Some(doc.value().trim().to_string()),
+            _ => None,
+        })
+        .collect();
+
+    let paragraphs: Vec<_> = lines
+        .split(String::is_empty)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| paragraph.join(" "))
+        .collect();
+    (!paragraphs.is_empty()).then(|| paragraphs.join("\n\n"))
+}
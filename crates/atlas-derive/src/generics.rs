@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{parse_quote, GenericParam, Generics, Type};
+
+use crate::url::{get_inner_type, is_option_type, is_vec_type};
+
+/// The type of the values of a field: `T` for `T`, `Option<T>`, `Vec<T>` and `Option<Vec<T>>`.
+pub(crate) fn value_type(ty: &Type) -> &Type {
+    let ty = match get_inner_type(ty) {
+        Some(inner) if is_option_type(ty) => inner,
+        _ => ty,
+    };
+    match get_inner_type(ty) {
+        Some(inner) if is_vec_type(ty) => inner,
+        _ => ty,
+    }
+}
+
+/// Checks if `tokens` mention any of `params`.
+fn mentions(tokens: TokenStream, params: &HashSet<String>) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => params.contains(&ident.to_string()),
+        TokenTree::Group(group) => mentions(group.stream(), params),
+        _ => false,
+    })
+}
+
+/// Adds `ty: bound` to the where clause of `generics` for each of `types` which mentions a
+/// generic parameter. Types without generic parameters are checked by the compiler anyway.
+///
+/// Bounding field types rather than type parameters also covers borrowed fields: an impl
+/// needing `&'a str: FromStr` simply doesn't apply.
+pub(crate) fn with_bounds<'a>(
+    generics: &Generics,
+    types: impl IntoIterator<Item = &'a Type>,
+    bound: TokenStream,
+) -> Generics {
+    let params: HashSet<_> = generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Type(param) => param.ident.to_string(),
+            GenericParam::Lifetime(param) => param.lifetime.ident.to_string(),
+            GenericParam::Const(param) => param.ident.to_string(),
+        })
+        .collect();
+
+    let mut generics = generics.clone();
+    let mut seen = HashSet::new();
+    for ty in types {
+        let tokens = ty.to_token_stream();
+        if mentions(tokens.clone(), &params) && seen.insert(tokens.to_string()) {
+            generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote! { #ty: #bound });
+        }
+    }
+    generics
+}
@@ -1,3 +1,9 @@
+mod attrs;
+mod case;
+mod generics;
+mod map_args;
+mod map_enum;
+mod runtime;
 mod url;
 mod try_from_map;
 
@@ -8,7 +14,17 @@ pub fn derive_atlas_url(input: TokenStream) -> TokenStream {
     url::derive_atlas_url_impl(input)
 }
 
-#[proc_macro_derive(TryFromMap)]
+#[proc_macro_derive(TryFromMap, attributes(map))]
 pub fn derive_try_from_map(input: TokenStream) -> TokenStream {
     try_from_map::derive_try_from_map_impl(input)
 }
+
+#[proc_macro_derive(MapEnum, attributes(map))]
+pub fn derive_map_enum(input: TokenStream) -> TokenStream {
+    map_enum::derive_map_enum_impl(input)
+}
+
+#[proc_macro_derive(MapArgs, attributes(map))]
+pub fn derive_map_args(input: TokenStream) -> TokenStream {
+    map_args::derive_map_args_impl(input)
+}
@@ -0,0 +1,139 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, Type};
+
+use crate::attrs::{doc_comment, MapContainerAttrs, MapFieldAttrs, VecStyle};
+use crate::case::RenameRule;
+use crate::generics::with_bounds;
+use crate::runtime::runtime_crate;
+use crate::url::{get_inner_type, is_option_type, is_vec_type};
+
+/// The help of a field from its doc comment: the first paragraph, and the whole comment as the
+/// long help if it has more paragraphs.
+fn doc_help(attrs: &[Attribute]) -> (Option<String>, Option<String>) {
+    let Some(doc) = doc_comment(attrs) else {
+        return (None, None);
+    };
+    match doc.split_once("\n\n") {
+        Some((help, _)) => (Some(help.to_string()), Some(doc)),
+        None => (Some(doc), None),
+    }
+}
+
+/// Implements the `MapArgs` derive macro, which implements the `MapArgs` trait of the runtime
+/// crate's `clap` feature for a struct also deriving `TryFromMap`, generating a clap `Arg` for
+/// each field.
+///
+/// The ids of the arguments are the keys of the fields, so their values are read like a map,
+/// and their long flags are the kebab-case names of the fields. Fields are required unless
+/// they're `Option`s or have `#[map(default)]`, `Vec`s can be repeated, `bool`s are flags which
+/// don't take a value, and the help comes from the doc comments. Nested fields add the
+/// arguments of their type, prefixing the flags with e.g. `--filter-` for
+/// `#[map(prefix = "filter.")]`.
+pub(crate) fn derive_map_args_impl(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(expanded) => expanded.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let krate = runtime_crate();
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "MapArgs can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => return Err(syn::Error::new(name.span(), "MapArgs can only be derived for structs")),
+    };
+
+    let container = MapContainerAttrs::parse(&input.attrs)?;
+    let mut args = Vec::new();
+    let mut nested_types = Vec::new();
+    for field in fields {
+        let attrs = MapFieldAttrs::parse(field)?;
+        let ty = &field.ty;
+        if attrs.is_nested() {
+            let prefix = attrs.prefix.as_deref().unwrap_or("");
+            args.push(quote! {
+                args.extend(<#ty as #krate::MapArgs>::args(
+                    &#krate::__private::prefixed(prefix, #prefix),
+                ));
+            });
+            nested_types.push(ty);
+            continue;
+        }
+
+        let key = attrs.effective_name(field, container.rename_all);
+        let key = attrs.vec.unwrap_or(VecStyle::Repeat).key(&key);
+        let field_name = field.ident.as_ref().expect("named field").unraw().to_string();
+        let long = RenameRule::Kebab.apply(&field_name);
+        let value_name = RenameRule::ScreamingSnake.apply(&field_name);
+
+        let is_option = is_option_type(ty);
+        let inner = match get_inner_type(ty) {
+            Some(inner) if is_option => inner,
+            _ => ty,
+        };
+        let is_flag = !is_option && matches!(inner, Type::Path(path) if path.path.is_ident("bool"));
+        let action = if is_flag {
+            quote! { .action(#krate::clap::ArgAction::SetTrue) }
+        } else if is_vec_type(inner) {
+            quote! { .action(#krate::clap::ArgAction::Append).value_name(#value_name) }
+        } else {
+            quote! { .action(#krate::clap::ArgAction::Set).value_name(#value_name) }
+        };
+        // Flags are `false` when they're not given
+        let required = !is_option && !is_flag && attrs.default.is_none();
+        let (help, long_help) = doc_help(&field.attrs);
+        let help = help.map(|help| quote! { .help(#help) });
+        let long_help = long_help.map(|long_help| quote! { .long_help(#long_help) });
+
+        args.push(quote! {
+            args.push(
+                #krate::clap::Arg::new(#krate::__private::prefixed(prefix, #key).into_owned())
+                    .long(#krate::__private::prefixed_long(prefix, #long))
+                    .required(#required)
+                    #action
+                    #help
+                    #long_help
+            );
+        });
+    }
+
+    let self_type: Type = {
+        let (_, ty_generics, _) = input.generics.split_for_impl();
+        parse_quote! { #name #ty_generics }
+    };
+    let generics = with_bounds(&input.generics, [&self_type], quote! { #krate::TryFromMap });
+    let generics = with_bounds(&generics, nested_types, quote! { #krate::MapArgs });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = if args.is_empty() {
+        quote! {
+            let _ = prefix;
+            Vec::new()
+        }
+    } else {
+        quote! {
+            let mut args = Vec::new();
+            #(#args)*
+            args
+        }
+    };
+    Ok(quote! {
+        impl #impl_generics #krate::MapArgs for #name #ty_generics #where_clause {
+            fn args(prefix: &str) -> Vec<#krate::clap::Arg> {
+                #body
+            }
+        }
+    })
+}
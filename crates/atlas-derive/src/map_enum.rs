@@ -0,0 +1,81 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+use crate::attrs::{MapContainerAttrs, MapVariantAttrs};
+use crate::runtime::runtime_crate;
+
+/// Implements the `MapEnum` derive macro, which implements `FromStr` and `Display` for an enum
+/// without fields, so it can be a field of `TryFromMap` and `AtlasURL` structs.
+///
+/// The names of the variants are converted with `#[map(rename_all = "...")]` on the enum or
+/// replaced with `#[map(rename = "...")]` on the variant. Parsing ignores the ASCII case.
+pub(crate) fn derive_map_enum_impl(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(expanded) => expanded.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let krate = runtime_crate();
+    let name = &input.ident;
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(name.span(), "MapEnum only supports enums"));
+    };
+
+    let container = MapContainerAttrs::parse(&input.attrs)?;
+    if container.deny_unknown_fields || container.single {
+        return Err(syn::Error::new(
+            name.span(),
+            "`deny_unknown_fields` and `single` aren't supported on enums",
+        ));
+    }
+
+    let mut variants = Vec::new();
+    let mut names = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                &variant.fields,
+                "MapEnum only supports variants without fields",
+            ));
+        }
+        let attrs = MapVariantAttrs::parse(&variant.attrs)?;
+        let ident = &variant.ident;
+        let name = attrs.rename.unwrap_or_else(|| {
+            let name = ident.to_string();
+            match container.rename_all {
+                Some(rule) => rule.apply_to_variant(&name),
+                None => name,
+            }
+        });
+        variants.push(ident);
+        names.push(name);
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics std::str::FromStr for #name #ty_generics #where_clause {
+            type Err = #krate::ParseEnumError;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                #(
+                    if value.eq_ignore_ascii_case(#names) {
+                        return Ok(Self::#variants);
+                    }
+                )*
+                Err(#krate::ParseEnumError::new(value, &[#(#names),*]))
+            }
+        }
+
+        impl #impl_generics std::fmt::Display for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(match self {
+                    #(Self::#variants => #names,)*
+                })
+            }
+        }
+    })
+}
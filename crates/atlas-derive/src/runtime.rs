@@ -0,0 +1,18 @@
+use proc_macro2::TokenStream;
+use proc_macro_crate::{crate_name, FoundCrate};
+use quote::{format_ident, quote};
+
+/// The path of the runtime support crate in the generated code, like `$crate` in a
+/// `macro_rules!` macro: `atlas_derive_core` if the crate using the derive depends on it, or
+/// else `atlas_core`, which re-exports it, under the names they're renamed to.
+pub(crate) fn runtime_crate() -> TokenStream {
+    let found = crate_name("atlas-derive-core").or_else(|_| crate_name("atlas-core"));
+    match found {
+        Ok(FoundCrate::Itself) => quote! { crate },
+        Ok(FoundCrate::Name(name)) => {
+            let name = format_ident!("{}", name);
+            quote! { ::#name }
+        }
+        Err(_) => quote! { ::atlas_core },
+    }
+}
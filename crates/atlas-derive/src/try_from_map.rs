@@ -1,136 +1,477 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, DeriveInput, Data, Fields, Type, PathArguments, GenericArgument};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, DeriveInput, Data, DataStruct, Fields, Type};
 
-pub(crate) fn derive_try_from_map_impl(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
-    let name = &input.ident;
-    
-    let fields = match &input.data {
-        Data::Struct(data) => {
-            match &data.fields {
-                Fields::Named(fields) => &fields.named,
-                _ => panic!("TryFromMap can only be derived for structs with named fields"),
-            }
+use crate::attrs::{is_vec_field, FieldDefault, MapContainerAttrs, MapFieldAttrs, VecStyle};
+use crate::generics::{value_type, with_bounds};
+use crate::runtime::runtime_crate;
+use crate::url::{get_inner_type, is_option_type, is_vec_type};
+
+/// Generates an iterator over the `&str` elements in `values`, splitting joined values.
+fn vec_elements(style: VecStyle) -> proc_macro2::TokenStream {
+    match style.separator() {
+        Some(separator) => quote! {
+            values
+                .iter()
+                .map(AsRef::<str>::as_ref)
+                .filter(|v| !v.is_empty())
+                .flat_map(|v| v.split(#separator))
         },
-        _ => panic!("TryFromMap can only be derived for structs"),
-    };
-
-    let field_extractions = fields.iter().map(|field| {
-        let field_name = field.ident.as_ref().unwrap();
-        let field_name_str = field_name.to_string();
-        let field_type = &field.ty;
-        
-        let extraction = if is_option_type(field_type) {
-            let inner_type = get_type_from_option(field_type);
-            if is_vec_type(inner_type) {
-                quote! {
-                    let #field_name = if let Some(values) = value.get(#field_name_str) {
-                        if values.is_empty() {
-                            None
-                        } else {
-                            Some(values.iter().map(|v| v.parse().map_err(|_| atlas_derive_core::TryFromMapError::ParseError {
-                                field: #field_name_str.to_string(),
-                                value: v.clone(),
-                            })).collect::<Result<Vec<_>, _>>()?)
-                        }
-                    } else {
-                        None
-                    };
-                }
-            } else {
-                quote! {
-                    let #field_name = if let Some(values) = value.get(#field_name_str) {
-                        if values.is_empty() {
-                            None
-                        } else {
-                            Some(values[0].parse().map_err(|_| atlas_derive_core::TryFromMapError::ParseError {
-                                field: #field_name_str.to_string(),
-                                value: values[0].clone(),
-                            })?)
-                        }
-                    } else {
-                        None
-                    };
-                }
-            }
-        } else if is_vec_type(field_type) {
+        None => quote! { values.iter().map(AsRef::<str>::as_ref) },
+    }
+}
+
+/// Checks if a type is a `bool`, whose values are parsed as flags.
+fn is_bool_type(ty: &Type) -> bool {
+    matches!(ty, Type::Path(type_path) if type_path.path.is_ident("bool"))
+}
+
+/// A field read from a map of values.
+pub(crate) struct MapField<'a> {
+    /// The variable the value is bound to.
+    pub(crate) binding: &'a syn::Ident,
+    pub(crate) key: &'a str,
+    pub(crate) ty: &'a Type,
+    pub(crate) vec_style: VecStyle,
+    pub(crate) default: Option<&'a FieldDefault>,
+    pub(crate) parse_with: Option<&'a syn::ExprPath>,
+    /// Several values are an error instead of taking the first one.
+    pub(crate) single: bool,
+}
+
+/// Generates an expression extracting `field` from the values under its key in the map
+/// `value`, as a `Result<T, TryFromMapError>`. The key is prefixed with `prefix` if `prefixed`.
+fn field_extraction(field: &MapField, prefixed: bool) -> proc_macro2::TokenStream {
+    let krate = runtime_crate();
+    let field_type = field.ty;
+    let vec_style = field.vec_style;
+    let default = field.default.map(|default| match default {
+        FieldDefault::Trait => quote! { Default::default() },
+        FieldDefault::Path(path) => quote! { #path() },
+    });
+
+    // `vec` styles are only allowed on `Vec`s, other keys are unchanged
+    let key = vec_style.key(field.key);
+    let key = if prefixed {
+        quote! {
+            let key = #krate::__private::prefixed(prefix, #key);
+            let key: &str = &key;
+        }
+    } else {
+        quote! { let key = #key; }
+    };
+    let is_flag = field.parse_with.is_none() && is_bool_type(value_type(field_type));
+    let parse = match field.parse_with {
+        Some(path) => quote! { #path(v) },
+        None if is_flag => quote! { #krate::parse_flag(v) },
+        None => quote! { v.parse() },
+    };
+    let parse = quote! {
+        #parse.map_err(|e| #krate::TryFromMapError::ParseError {
+            field: key.to_string(),
+            value: v.to_string(),
+            source: Into::into(e),
+        })
+    };
+    let elements = vec_elements(vec_style);
+    let parse_elements = quote! {
+        #elements.map(|v| #parse).collect::<Result<Vec<_>, _>>()
+    };
+    let missing = quote! {
+        Err(#krate::TryFromMapError::MissingField(key.to_string()))
+    };
+
+    // The value of a field which isn't a `Vec`: `None` if the key is missing, and `Some(None)`
+    // if it has no values
+    let scalar = |arms: proc_macro2::TokenStream| {
+        let first = quote! { .map(|values| values.first().map(AsRef::<str>::as_ref)) };
+        if field.single {
             quote! {
-                let #field_name = value.get(#field_name_str)
-                    .ok_or_else(|| atlas_derive_core::TryFromMapError::MissingField(#field_name_str.to_string()))?
-                    .iter()
-                    .map(|v| v.parse().map_err(|_| atlas_derive_core::TryFromMapError::ParseError {
-                        field: #field_name_str.to_string(),
-                        value: v.clone(),
-                    }))
-                    .collect::<Result<Vec<_>, _>>()?;
+                match value.get(key).map(Vec::as_slice) {
+                    Some([_, _, ..]) => Err(#krate::TryFromMapError::TooManyValues(key.to_string())),
+                    values => match values #first { #arms },
+                }
             }
         } else {
             quote! {
-                let #field_name = value.get(#field_name_str)
-                    .ok_or_else(|| atlas_derive_core::TryFromMapError::MissingField(#field_name_str.to_string()))?
-                    .get(0)
-                    .ok_or_else(|| atlas_derive_core::TryFromMapError::NoValuesInField(#field_name_str.to_string()))?
-                    .parse()
-                    .map_err(|_| atlas_derive_core::TryFromMapError::ParseError {
-                        field: #field_name_str.to_string(),
-                        value: value[#field_name_str][0].clone(),
-                    })?;
+                match value.get(key) #first { #arms }
             }
-        };
-        
-        extraction
-    });
-
-    let field_names = fields.iter().map(|field| {
-        let field_name = field.ident.as_ref().unwrap();
-        quote! { #field_name }
-    });
-
-    let expanded = quote! {
-        impl TryFrom<std::collections::HashMap<String, Vec<String>>> for #name {
-            type Error = atlas_derive_core::TryFromMapError;
+        }
+    };
 
-            fn try_from(value: std::collections::HashMap<String, Vec<String>>) -> Result<Self, Self::Error> {
-                #(#field_extractions)*
+    // A `bool` whose key has no values is set, like the flag without a value in
+    // `?includeCount`, while an `Option<bool>` is `None`
+    let no_values = |otherwise: proc_macro2::TokenStream| {
+        if is_flag {
+            quote! { Ok(true) }
+        } else {
+            otherwise
+        }
+    };
 
-                Ok(Self {
-                    #(#field_names),*
+    let extraction = if is_option_type(field_type) {
+        let default = default.unwrap_or_else(|| quote! { None });
+        let inner_type = get_inner_type(field_type).expect("Option has a type argument");
+        if is_vec_type(inner_type) {
+            quote! {
+                match value.get(key).filter(|values| !values.is_empty()) {
+                    Some(values) => #parse_elements.map(Some),
+                    None => Ok(#default),
+                }
+            }
+        } else {
+            scalar(quote! {
+                Some(Some(v)) => #parse.map(Some),
+                Some(None) | None => Ok(#default),
+            })
+        }
+    } else if is_vec_type(field_type) {
+        let default = default.map_or(missing, |default| quote! { Ok(#default) });
+        quote! {
+            match value.get(key) {
+                Some(values) => #parse_elements,
+                None => #default,
+            }
+        }
+    } else {
+        match default {
+            Some(default) => {
+                let no_values = no_values(quote! { Ok(#default) });
+                scalar(quote! {
+                    Some(Some(v)) => #parse,
+                    Some(None) => #no_values,
+                    None => Ok(#default),
+                })
+            }
+            None => {
+                let no_values = no_values(quote! {
+                    Err(#krate::TryFromMapError::NoValuesInField(key.to_string()))
+                });
+                scalar(quote! {
+                    Some(Some(v)) => #parse,
+                    Some(None) => #no_values,
+                    None => #missing,
                 })
             }
         }
     };
-
-    TokenStream::from(expanded)
+    quote! {
+        {
+            #key
+            #extraction
+        }
+    }
 }
 
-fn is_option_type(ty: &Type) -> bool {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.first() {
-            return segment.ident == "Option";
+/// Generates the code binding each of `fields` to its `binding`, extracted from the map
+/// `value`: an `Option` which is `None` if the field failed, with its error pushed to `errors`.
+pub(crate) fn fields_extraction(fields: &[MapField], prefixed: bool) -> proc_macro2::TokenStream {
+    let extractions = fields.iter().map(|field| {
+        let binding = field.binding;
+        let extraction = field_extraction(field, prefixed);
+        quote! {
+            let #binding = match #extraction {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    errors.push(e);
+                    None
+                }
+            };
         }
-    }
-    false
+    });
+    quote! { #(#extractions)* }
 }
 
-fn is_vec_type(ty: &Type) -> bool {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.first() {
-            return segment.ident == "Vec";
+/// Generates the code adding the values of `field`, bound by reference to its `binding`, to
+/// `map`, so that [`field_extraction`] reads them back.
+fn field_write(field: &MapField) -> proc_macro2::TokenStream {
+    let krate = runtime_crate();
+    let binding = field.binding;
+    let key = field.vec_style.key(field.key);
+    let key = quote! { #krate::__private::prefixed(prefix, #key).into_owned() };
+    let values = match field.vec_style.separator() {
+        Some(separator) => quote! {
+            if values.is_empty() {
+                Vec::new()
+            } else {
+                vec![values.iter().map(ToString::to_string).collect::<Vec<_>>().join(#separator)]
+            }
+        },
+        None => quote! { values.iter().map(ToString::to_string).collect() },
+    };
+
+    let (is_option, ty) = match get_inner_type(field.ty) {
+        Some(inner) if is_option_type(field.ty) => (true, inner),
+        _ => (false, field.ty),
+    };
+    let insert = if is_vec_type(ty) {
+        quote! { map.insert(#key, #values); }
+    } else {
+        quote! { map.insert(#key, vec![values.to_string()]); }
+    };
+    if is_option {
+        quote! {
+            if let Some(values) = #binding {
+                #insert
+            }
+        }
+    } else {
+        quote! {
+            let values = #binding;
+            #insert
         }
     }
-    false
 }
 
-fn get_type_from_option(ty: &Type) -> &Type {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.first() {
-            if let PathArguments::AngleBracketed(args) = &segment.arguments {
-                if let Some(GenericArgument::Type(inner_type)) = args.args.first() {
-                    return inner_type;
+/// The types of the `FromStr` errors of `types`, whose bound lets them be kept as the source
+/// of a `TryFromMapError::ParseError`.
+pub(crate) fn parse_error_types<'a>(types: impl IntoIterator<Item = &'a Type>) -> Vec<Type> {
+    types
+        .into_iter()
+        .map(|ty| parse_quote! { <#ty as std::str::FromStr>::Err })
+        .collect()
+}
+
+/// The bound of the types returned by [`parse_error_types`].
+pub(crate) fn parse_error_bound() -> proc_macro2::TokenStream {
+    quote! { Into<Box<dyn std::error::Error + Send + Sync>> }
+}
+
+/// Implements the `TryFromMap` derive macro, which builds a struct from the values of each key
+/// of e.g. query parameters or form data. It implements the `TryFromMap` trait, which also
+/// builds it from a query string, a `Url` or `(key, value)` pairs, and
+/// `TryFrom<HashMap<String, Vec<String>>>`.
+///
+/// With `#[map(to_map)]` on the struct, the reverse conversion is implemented by `ToMap` and
+/// `From<&T> for HashMap<String, Vec<String>>`, writing the values with `Display`, which the
+/// types of the fields and the nested fields' `ToMap` then need. It round-trips as long as
+/// `Display` is the reverse of `FromStr` or of the `parse_with` function, joined values don't
+/// contain their separator and `Option<Vec>` fields aren't `Some` of an empty `Vec`, which
+/// reads back as `None`.
+///
+/// Fields are looked up by name, converted with `#[map(rename_all = "...")]` on the struct or
+/// replaced with `#[map(rename = "...")]`, and parsed with `FromStr`, with `parse_flag` for
+/// `bool`s, which are also `true` if their key has no values, or with the
+/// `fn(&str) -> Result<T, E>` given by `#[map(parse_with = "path")]`.
+/// Missing fields are an error unless they are `Option`s or have `#[map(default)]` or
+/// `#[map(default = "path")]`, e.g. flags which are `false` unless they're given.
+///
+/// Other keys are ignored and fields which aren't `Vec`s take their first value, unless the
+/// struct has `#[map(deny_unknown_fields)]`, or the struct or the field has `#[map(single)]`.
+///
+/// Fields whose type also derives `TryFromMap` are read from the same keys with
+/// `#[map(flatten)]`, or from the keys starting with a prefix with `#[map(prefix = "filter.")]`.
+///
+/// The errors of all the fields are returned at once as a `TryFromMapErrors`, and parse errors
+/// keep the error of `FromStr` or of the `parse_with` function as their source.
+pub(crate) fn derive_try_from_map_impl(input: TokenStream) -> TokenStream {
+    let krate = runtime_crate();
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields), .. }) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(
+                &input,
+                "TryFromMap can only be derived for structs with named fields",
+            )
+            .into_compile_error()
+            .into()
+        }
+    };
+
+    let container = match MapContainerAttrs::parse(&input.attrs) {
+        Ok(container) => container,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let attrs = fields.iter().map(MapFieldAttrs::parse).collect::<syn::Result<Vec<_>>>();
+    let attrs = match attrs {
+        Ok(attrs) => attrs,
+        Err(e) => return e.into_compile_error().into(),
+    };
+
+    let bindings: Vec<_> = (0..fields.len()).map(|i| format_ident!("field_{}", i)).collect();
+    let keys: Vec<_> = fields
+        .iter()
+        .zip(&attrs)
+        .map(|(field, attrs)| attrs.effective_name(field, container.rename_all))
+        .collect();
+    let map_fields: Vec<_> = fields
+        .iter()
+        .zip(&attrs)
+        .enumerate()
+        .filter(|(_, (_, attrs))| !attrs.is_nested())
+        .map(|(i, (field, attrs))| MapField {
+            binding: &bindings[i],
+            key: &keys[i],
+            ty: &field.ty,
+            vec_style: attrs.vec.unwrap_or(VecStyle::Repeat),
+            default: attrs.default.as_ref(),
+            parse_with: attrs.parse_with.as_ref(),
+            single: attrs.single || (container.single && !is_vec_field(field)),
+        })
+        .collect();
+    let fields_extraction = fields_extraction(&map_fields, true);
+    let field_keys = map_fields.iter().map(|field| field.vec_style.key(field.key));
+
+    // Nested fields are read with the prefix of the struct followed by their own
+    let nested: Vec<_> = fields
+        .iter()
+        .zip(&attrs)
+        .zip(&bindings)
+        .filter(|((_, attrs), _)| attrs.is_nested())
+        .map(|((field, attrs), binding)| (&field.ty, attrs.prefix.as_deref().unwrap_or(""), binding))
+        .collect();
+    let nested_extractions = nested.iter().map(|(ty, prefix, binding)| {
+        quote! {
+            let #binding = <#ty as #krate::TryFromMap>::read_fields(
+                value,
+                &#krate::__private::prefixed(prefix, #prefix),
+                errors,
+            );
+        }
+    });
+    let nested_writes = nested.iter().map(|(ty, prefix, binding)| {
+        quote! {
+            <#ty as #krate::ToMap>::write_fields(
+                #binding,
+                &#krate::__private::prefixed(prefix, #prefix),
+                map,
+            );
+        }
+    });
+    let field_writes = map_fields.iter().map(field_write);
+    let nested_fields = nested.iter().map(|(ty, prefix, _)| {
+        quote! {
+            key.strip_prefix(#prefix).is_some_and(<#ty as #krate::TryFromMap>::has_field)
+        }
+    });
+
+    let field_values: Vec<_> = fields
+        .iter()
+        .zip(&bindings)
+        .map(|(field, binding)| {
+            let field_name = field.ident.as_ref().unwrap();
+            quote! { #field_name: #binding }
+        })
+        .collect();
+    let read_fields = if fields.is_empty() {
+        quote! {
+            let _ = (value, prefix, errors);
+            Some(Self {})
+        }
+    } else {
+        quote! {
+            #fields_extraction
+            #(#nested_extractions)*
+            let (#(Some(#bindings),)*) = (#(#bindings,)*) else {
+                return None;
+            };
+            Some(Self {
+                #(#field_values),*
+            })
+        }
+    };
+    let write_fields = if fields.is_empty() {
+        quote! { let _ = (prefix, map); }
+    } else {
+        quote! {
+            let Self { #(#field_values),* } = self;
+            #(#field_writes)*
+            #(#nested_writes)*
+        }
+    };
+    let deny_unknown_fields = if container.deny_unknown_fields {
+        quote! { const DENY_UNKNOWN_FIELDS: bool = true; }
+    } else {
+        quote! {}
+    };
+
+    let parsed_types: Vec<_> = map_fields
+        .iter()
+        .filter(|field| field.parse_with.is_none())
+        .map(|field| value_type(field.ty))
+        .collect();
+    let generics = with_bounds(&input.generics, parsed_types.iter().copied(), quote! { std::str::FromStr });
+    let generics = with_bounds(&generics, &parse_error_types(parsed_types), parse_error_bound());
+    let generics = with_bounds(
+        &generics,
+        map_fields
+            .iter()
+            .filter(|field| matches!(field.default, Some(FieldDefault::Trait)))
+            .map(|field| field.ty),
+        quote! { Default },
+    );
+    let generics = with_bounds(
+        &generics,
+        nested.iter().map(|(ty, _, _)| *ty),
+        quote! { #krate::TryFromMap },
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Writing the values needs `Display` instead of `FromStr`
+    let to_map_generics = with_bounds(
+        &input.generics,
+        map_fields.iter().map(|field| value_type(field.ty)),
+        quote! { std::fmt::Display },
+    );
+    let to_map_generics = with_bounds(
+        &to_map_generics,
+        nested.iter().map(|(ty, _, _)| *ty),
+        quote! { #krate::ToMap },
+    );
+    let (to_map_impl_generics, _, to_map_where_clause) = to_map_generics.split_for_impl();
+    let to_map = if container.to_map {
+        quote! {
+            impl #to_map_impl_generics #krate::ToMap for #name #ty_generics #to_map_where_clause {
+                fn write_fields(&self, prefix: &str, map: &mut std::collections::HashMap<String, Vec<String>>) {
+                    #write_fields
+                }
+            }
+
+            impl #to_map_impl_generics From<&#name #ty_generics> for std::collections::HashMap<String, Vec<String>> #to_map_where_clause {
+                fn from(value: &#name #ty_generics) -> Self {
+                    #krate::ToMap::to_map(value)
                 }
             }
         }
-    }
-    panic!("Not an Option type")
+    } else {
+        quote! {}
+    };
+
+    let expanded = quote! {
+        impl #impl_generics #krate::TryFromMap for #name #ty_generics #where_clause {
+            #deny_unknown_fields
+
+            fn read_fields<__K, __V, __S>(
+                value: &std::collections::HashMap<__K, Vec<__V>, __S>,
+                prefix: &str,
+                errors: &mut Vec<#krate::TryFromMapError>,
+            ) -> Option<Self>
+            where
+                __K: std::borrow::Borrow<str> + std::hash::Hash + Eq,
+                __V: AsRef<str>,
+                __S: std::hash::BuildHasher,
+            {
+                #read_fields
+            }
+
+            fn has_field(key: &str) -> bool {
+                [#(#field_keys),*].contains(&key) #(|| #nested_fields)*
+            }
+        }
+
+        #to_map
+
+        impl #impl_generics TryFrom<std::collections::HashMap<String, Vec<String>>> for #name #ty_generics #where_clause {
+            type Error = #krate::TryFromMapErrors;
+
+            fn try_from(value: std::collections::HashMap<String, Vec<String>>) -> Result<Self, Self::Error> {
+                <Self as #krate::TryFromMap>::try_from_map(&value)
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
 }
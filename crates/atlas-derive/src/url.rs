@@ -1,23 +1,32 @@
+use std::collections::HashSet;
+
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote, ToTokens};
 use syn::{
-    Data, DeriveInput, Expr, Fields, GenericArgument, Lit, PathArguments, Type, parse_macro_input,
+    Data, DeriveInput, Field, Fields, GenericArgument, Generics, LitStr, PathArguments, Type,
+    parse_macro_input, parse_quote,
 };
 
+use crate::attrs::{doc_comment, ContainerAttrs, FieldAttrs, Placement, VecStyle};
+use crate::case::RenameRule;
+use crate::generics::{value_type, with_bounds};
+use crate::runtime::runtime_crate;
+use crate::try_from_map::{fields_extraction, parse_error_bound, parse_error_types, MapField};
+
 /// Checks if a type is an `Option<T>`.
-fn is_option_type(ty: &Type) -> bool {
+pub(crate) fn is_option_type(ty: &Type) -> bool {
     matches!(ty, Type::Path(type_path) if type_path.path.segments.last()
-        .map_or(false, |seg| seg.ident == "Option"))
+        .is_some_and(|seg| seg.ident == "Option"))
 }
 
 /// Checks if a type is a `Vec<T>`.
-fn is_vec_type(ty: &Type) -> bool {
+pub(crate) fn is_vec_type(ty: &Type) -> bool {
     matches!(ty, Type::Path(type_path) if type_path.path.segments.last()
-        .map_or(false, |seg| seg.ident == "Vec"))
+        .is_some_and(|seg| seg.ident == "Vec"))
 }
 
 /// Extracts the inner type from an `Option<T>` or `Vec<T>`.
-fn get_inner_type(ty: &Type) -> Option<&Type> {
+pub(crate) fn get_inner_type(ty: &Type) -> Option<&Type> {
     if let Type::Path(type_path) = ty {
         type_path.path.segments.last().and_then(|segment| {
             match &segment.arguments { PathArguments::AngleBracketed(args) => {
@@ -37,213 +46,1006 @@ fn get_inner_type(ty: &Type) -> Option<&Type> {
     }
 }
 
-/// Checks if a field name is used as a path parameter in the URL pattern.
-fn is_path_param(field_name: &str, url_pattern: &str) -> bool {
-    url_pattern.contains(&format!("{{{}}}", field_name))
+/// A piece of a URL pattern.
+enum PatternPart {
+    Literal(String),
+    /// A `{field}` placeholder, substituted with the value of the field.
+    Placeholder(String),
+}
+
+/// Splits a URL pattern like `/groups/{group_id}/clusters` into literals and placeholders.
+///
+/// Errors point at the pattern literal, as a string literal can't be spanned partially on
+/// stable Rust.
+fn parse_pattern(pattern: &LitStr) -> syn::Result<Vec<PatternPart>> {
+    let value = pattern.value();
+    let mut parts = Vec::new();
+    let mut seen = HashSet::new();
+    let mut rest = value.as_str();
+
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err(syn::Error::new(
+                pattern.span(),
+                format!("unmatched `}}` in URL pattern `{value}`"),
+            ));
+        }
+        parts.push(PatternPart::Literal(rest[..start].to_string()));
+
+        let after_brace = &rest[start + 1..];
+        let end = match after_brace.find(['{', '}']) {
+            Some(end) if after_brace[end..].starts_with('}') => end,
+            _ => {
+                return Err(syn::Error::new(
+                    pattern.span(),
+                    format!("unclosed `{{` in URL pattern `{value}`"),
+                ))
+            }
+        };
+
+        let placeholder = after_brace[..end].trim();
+        if syn::parse_str::<syn::Ident>(placeholder).is_err() {
+            return Err(syn::Error::new(
+                pattern.span(),
+                format!("invalid placeholder `{{{placeholder}}}`, expected a field name"),
+            ));
+        }
+        if !seen.insert(placeholder.to_string()) {
+            return Err(syn::Error::new(
+                pattern.span(),
+                format!("placeholder `{{{placeholder}}}` is used more than once"),
+            ));
+        }
+        parts.push(PatternPart::Placeholder(placeholder.to_string()));
+
+        rest = &after_brace[end + 1..];
+    }
+    parts.push(PatternPart::Literal(rest.to_string()));
+
+    Ok(parts)
+}
+
+/// Generates the code appending the non-empty `Vec` in `values` to the query, preserving
+/// the order of the elements.
+fn append_vec(name: &str, style: VecStyle) -> proc_macro2::TokenStream {
+    let krate = runtime_crate();
+    let key = style.key(name);
+    match style.separator() {
+        Some(separator) => quote! {
+            if !values.is_empty() {
+                #krate::push_query_values(&mut query, #key, values, #separator);
+            }
+        },
+        None => quote! {
+            for value in values {
+                #krate::push_query_pair(&mut query, #key, value);
+            }
+        },
+    }
+}
+
+/// Generates the `PatternPart::Literal` for the `i`th part of a URL pattern.
+fn match_literal(i: usize, literal: &str) -> proc_macro2::TokenStream {
+    let krate = runtime_crate();
+    // `join_base_url` puts exactly one slash between the base URL and the pattern
+    let literal = if i == 0 {
+        format!("/{}", literal.trim_start_matches('/'))
+    } else {
+        literal.to_string()
+    };
+    quote! { #krate::PatternPart::Literal(#literal) }
+}
+
+/// The type of a path parameter, `T` for an `optional` `Option<T>`.
+fn path_param_type<'a>(field: &UrlField<'a>) -> &'a Type {
+    match get_inner_type(&field.field.ty) {
+        Some(inner) if field.attrs.optional => inner,
+        _ => &field.field.ty,
+    }
+}
+
+/// A field together with its `#[url(...)]` attributes.
+struct UrlField<'a> {
+    field: &'a Field,
+    ident: &'a syn::Ident,
+    /// The variable the field is bound to in the generated code, which can't clash with the
+    /// variables of the generated code itself.
+    binding: syn::Ident,
+    attrs: FieldAttrs,
+    /// The name used for placeholders and query keys.
+    name: String,
+}
+
+/// The generated code for the fields of a struct or of an enum variant.
+struct UrlExpansion<'a> {
+    pattern: LitStr,
+    /// The fields bound to their `binding`s when destructuring `self`, e.g.
+    /// `{ group_id: field_0, .. }`.
+    destructure: proc_macro2::TokenStream,
+    /// The body of `as_url` once the fields are bound.
+    as_url: proc_macro2::TokenStream,
+    /// The body of `from_url`, returning the value if the path of `url` matches the pattern.
+    from_url: proc_macro2::TokenStream,
+    /// The types of the values formatted into and parsed from the URL, whose parse errors are
+    /// kept as sources.
+    url_types: Vec<&'a Type>,
+    /// The types of the skipped and body fields, which are defaulted when parsing a URL.
+    default_types: Vec<&'a Type>,
+    /// The pattern with the placeholders named like the parameters, for `DescribeUrl`.
+    describe_path: String,
+    /// The `ParamDescriptor`s of the path and query parameters.
+    describe_params: Vec<proc_macro2::TokenStream>,
+    fields: Vec<UrlField<'a>>,
 }
 
 /// Implements the `AsUrl` derive macro which generates URL handling code.
 ///
 /// This macro generates an implementation of the `AsUrl` trait for structs,
 /// allowing them to be converted into URLs with proper handling of:
-/// - Path parameters (using {param} syntax in the URL pattern)
-/// - Query parameters (fields not used in the path)
-/// - Optional parameters (Option<T> fields)
-/// - Vector parameters (Vec<T> fields)
+/// - Path parameters (using {param} syntax in the URL pattern), percent-encoded unless the
+///   field is marked with `#[url(raw)]`
+/// - Query parameters (fields not used in the path, unless marked with `#[url(skip)]`)
+/// - Renaming with `#[url(rename = "...")]` on a field or `#[url(rename_all = "...")]` on the
+///   struct, which applies to both placeholders and query keys
+/// - Explicit placement with `#[url(path)]` or `#[url(query)]`
+/// - An `AtlasRequest` impl when the struct has `#[url(method = "...")]`, optionally with
+///   `version = "..."`, `response = Type` and a field marked with `#[url(body)]`
+/// - Optional query parameters (Option<T> fields), and optional path parameters marked with
+///   `#[url(optional)]` whose segment is left out when they are `None`
+/// - Vector parameters (Vec<T> fields), repeated in the query and comma-joined in the path
+///   unless `#[url(vec = "repeat" | "comma" | "pipe" | "brackets")]` says otherwise
+/// - A `DescribeUrl` impl when the struct has `#[url(describe)]`, describing the path template
+///   and the parameters with their doc comments, e.g. to emit an OpenAPI path item
+///
+/// It also implements `FromUrl` and `TryFrom<&Url>`, parsing such a URL back into the struct.
+/// This requires the path and query fields to implement `FromStr`, with errors converting into
+/// `Box<dyn Error + Send + Sync>` for the query fields, and the skipped and body fields to
+/// implement `Default`.
+///
+/// Generic and borrowed structs are supported: the impls are bounded on the field types which
+/// mention a generic parameter, so e.g. `FromUrl` isn't implemented for `&'a str` fields.
+///
+/// Enums of endpoints are supported with a URL pattern on each variant. `rename_all` on the
+/// enum applies to all variants, and parsing a URL picks the first variant whose pattern
+/// matches. Enums can't have a `method`, so they don't implement `AtlasRequest`.
 ///
 /// # Example
 /// ```ignore
 /// #[derive(AtlasURL)]
-/// #[url("/api/v1/users/{id}")]
+/// #[url("/api/v1/users/{id}", rename_all = "camelCase")]
 /// struct User {
 ///     id: u32,
 ///     name: Option<String>,
 ///     tags: Vec<String>,
+///     items_per_page: Option<u32>,
+///     #[url(skip)]
+///     cache_key: String,
+/// }
+///
+/// #[derive(AtlasURL)]
+/// enum UserEndpoint {
+///     #[url("/api/v1/users")]
+///     List { page: Option<u32> },
+///     #[url("/api/v1/users/{id}")]
+///     Get { id: u32 },
 /// }
 /// ```
 pub(crate) fn derive_atlas_url_impl(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    // Extract URL pattern from the #[url("...")] attribute
-    let url_pattern = input.attrs
-        .iter()
-        .find(|attr| attr.path().is_ident("url"))
-        .and_then(|attr| {
-            let expr = attr.parse_args::<Expr>().ok()?;
-            match expr {
-                Expr::Lit(expr_lit) => {
-                    match expr_lit.lit {
-                        Lit::Str(s) => Some(s.value()),
-                        _ => None
-                    }
-                },
-                _ => None
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let krate = runtime_crate();
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let name = &input.ident;
+
+    let (as_url, expansions) = match &input.data {
+        Data::Struct(data) => {
+            if container.pattern.is_none() {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "AtlasURL requires a URL pattern with #[url(\"/path/to/resource\")]",
+                ));
+            }
+            let expansion = expand_fields(&name.to_string(), quote! { Self }, &container, None, &data.fields)?;
+            let UrlExpansion { destructure, as_url, .. } = &expansion;
+            let as_url = quote! {
+                let Self #destructure = self;
+                #as_url
+            };
+            (as_url, vec![expansion])
+        }
+        Data::Enum(data) => {
+            if let Some(pattern) = &container.pattern {
+                return Err(syn::Error::new(
+                    pattern.span(),
+                    "the URL pattern of an enum goes on each variant",
+                ));
+            }
+            check_no_request(&container, name)?;
+            if data.variants.is_empty() {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "AtlasURL requires an enum with at least one variant",
+                ));
             }
-        })
-        .unwrap_or_else(|| {
-            panic!("AtlasURL requires a URL pattern with #[url(\"/path/to/resource\")]")
-        });
 
-    let name = &input.ident;
+            let expansions = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let attrs = ContainerAttrs::parse(&variant.attrs)?;
+                    if attrs.pattern.is_none() {
+                        return Err(syn::Error::new(
+                            variant.ident.span(),
+                            "AtlasURL requires a URL pattern on each variant with #[url(\"/path/to/resource\")]",
+                        ));
+                    }
+                    check_no_request(&attrs, &variant.ident)?;
+                    let ident = &variant.ident;
+                    let expansion = expand_fields(
+                        &format!("{name}::{ident}"),
+                        quote! { Self::#ident },
+                        &attrs,
+                        container.rename_all,
+                        &variant.fields,
+                    )?;
+                    // Rejects `body` fields, which require a `method`
+                    request_impl(ident, &input.generics, &attrs, &expansion.fields)?;
+                    Ok(expansion)
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
 
-    // Extract named fields from the struct
-    let fields = match &input.data {
-        Data::Struct(data) => match &data.fields {
-            Fields::Named(fields) => &fields.named,
-            _ => panic!("AtlasURL only supports named fields"),
-        },
-        _ => panic!("AtlasURL only supports structs"),
+            let arms = data.variants.iter().zip(&expansions).map(|(variant, expansion)| {
+                let ident = &variant.ident;
+                let UrlExpansion { destructure, as_url, .. } = expansion;
+                quote! {
+                    Self::#ident #destructure => {
+                        #as_url
+                    }
+                }
+            });
+            let as_url = quote! {
+                match self {
+                    #(#arms)*
+                }
+            };
+            (as_url, expansions)
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "AtlasURL only supports structs and enums",
+            ))
+        }
     };
 
-    let field_names: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
-    let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let generics = with_bounds(
+        &input.generics,
+        expansions.iter().flat_map(|e| e.url_types.iter().copied()),
+        quote! { std::fmt::Display },
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let from_url_impl = from_url_impl(name, &input.generics, &expansions);
+    let request_impl = match &input.data {
+        Data::Struct(_) => request_impl(name, &generics, &container, &expansions[0].fields)?,
+        _ => quote! {},
+    };
+    let describe_impl = match &input.data {
+        Data::Struct(_) => describe_impl(input, &container, &expansions[0]),
+        _ => quote! {},
+    };
+
+    // Generate the final implementation
+    Ok(quote! {
+        impl #impl_generics #krate::AsUrl for #name #ty_generics #where_clause {
+            fn as_url(
+                &self,
+                base_url: impl #krate::IntoBaseUrl,
+            ) -> Result<#krate::url::Url, #krate::AsUrlError> {
+                #as_url
+            }
+        }
+
+        #from_url_impl
+
+        #request_impl
+
+        #describe_impl
+    })
+}
+
+/// Rejects `method`, `version` and `response` on enums and their variants, as an
+/// `AtlasRequest` has a single method, and `describe`, as a `DescribeUrl` has a single path.
+fn check_no_request(attrs: &ContainerAttrs, ident: &syn::Ident) -> syn::Result<()> {
+    if attrs.method.is_some() || attrs.version.is_some() || attrs.response.is_some() {
+        return Err(syn::Error::new(
+            ident.span(),
+            "`method`, `version` and `response` aren't supported on enums",
+        ));
+    }
+    if attrs.describe {
+        return Err(syn::Error::new(ident.span(), "`describe` isn't supported on enums"));
+    }
+    Ok(())
+}
+
+/// Generates the URL handling code for the fields of `owner`, a struct or an enum variant
+/// built with `constructor`.
+fn expand_fields<'a>(
+    owner: &str,
+    constructor: proc_macro2::TokenStream,
+    container: &ContainerAttrs,
+    default_rename_all: Option<RenameRule>,
+    fields: &'a Fields,
+) -> syn::Result<UrlExpansion<'a>> {
+    let krate = runtime_crate();
+    let url_pattern = container.pattern.clone().expect("checked by the caller");
+    let pattern_parts = parse_pattern(&url_pattern)?;
+    let rename_all = container.rename_all.or(default_rename_all);
+
+    let fields = match fields {
+        Fields::Named(fields) => fields.named.iter().collect(),
+        Fields::Unit => Vec::new(),
+        fields => {
+            return Err(syn::Error::new_spanned(
+                fields,
+                "AtlasURL only supports named fields",
+            ))
+        }
+    };
 
-    // Generate code for path segments
-    let segments: Vec<_> = url_pattern
-        .split('{')
+    let fields = fields
+        .into_iter()
         .enumerate()
-        .map(|(i, s)| {
-            if i == 0 {
-                return quote! { url.push_str(#s); };
+        .map(|(i, field)| {
+            let attrs = FieldAttrs::parse(field)?;
+            Ok(UrlField {
+                field,
+                ident: field.ident.as_ref().unwrap(),
+                binding: format_ident!("field_{}", i),
+                name: attrs.effective_name(field, rename_all),
+                attrs,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    // Generate code for path segments, placeholders refer to the effective name of a field
+    // or to the field itself. `match_parts` is the pattern for `match_path` when parsing.
+    let mut path_fields = HashSet::new();
+    let mut placeholder_fields = Vec::new();
+    let mut segments = Vec::with_capacity(pattern_parts.len());
+    let mut match_parts = Vec::with_capacity(pattern_parts.len());
+    let mut describe_path = String::new();
+    for (i, part) in pattern_parts.iter().enumerate() {
+        let placeholder = match part {
+            PatternPart::Literal(literal) => {
+                describe_path.push_str(literal);
+                segments.push(quote! { path.push_str(#literal); });
+                match_parts.push(match_literal(i, literal));
+                continue;
             }
+            PatternPart::Placeholder(placeholder) => placeholder,
+        };
 
-            let (field, rest) = s.split_once('}')
-                .unwrap_or_else(|| panic!("Invalid URL pattern: missing closing brace"));
-            
-            let field = field.trim();
-            let field_ident = syn::Ident::new(field, proc_macro2::Span::call_site());
+        let field = fields
+            .iter()
+            .find(|f| f.name == *placeholder)
+            .or_else(|| fields.iter().find(|f| f.ident == placeholder))
+            .ok_or_else(|| {
+                syn::Error::new(
+                    url_pattern.span(),
+                    format!("unknown placeholder `{{{placeholder}}}`: `{owner}` has no field named `{placeholder}`"),
+                )
+            })?;
+        let field_ident = field.ident;
 
-            // Find the field type and generate appropriate serialization code
-            let field_type = fields
-                .iter()
-                .find(|f| f.ident.as_ref().unwrap() == &field_ident)
-                .map(|f| &f.ty);
-
-            match field_type {
-                Some(ty) if is_vec_type(ty) => quote! {
-                    url.push_str(&self.#field_ident.iter()
-                        .map(|v| v.to_string())
-                        .collect::<Vec<_>>()
-                        .join(","));
-                    url.push_str(#rest);
-                },
-                Some(ty) if is_option_type(ty) => quote! {
-                    if let Some(value) = self.#field_ident {
-                        url.push_str(&value.to_string());
+        if !path_fields.insert(field_ident) {
+            return Err(syn::Error::new(
+                url_pattern.span(),
+                format!("field `{field_ident}` is used by more than one placeholder"),
+            ));
+        }
+        if field.attrs.skip || field.attrs.body || field.attrs.placement == Placement::Query {
+            return Err(syn::Error::new(
+                field_ident.span(),
+                format!("`{field_ident}` is used as a path parameter but marked as `skip`, `body` or `query`"),
+            ));
+        }
+
+        let optional = field.attrs.optional;
+        if is_option_type(&field.field.ty) && !optional {
+            return Err(syn::Error::new_spanned(
+                &field.field.ty,
+                format!("`{placeholder}` is used as a path parameter and can't be an `Option`, unless it's marked with `#[url(optional)]` to leave out its segment when it's `None`"),
+            ));
+        }
+        if optional {
+            // The slash before the segment is only written along with its value
+            let before = match &pattern_parts[i - 1] {
+                PatternPart::Literal(before) => before.strip_suffix('/').filter(|before| {
+                    i > 1 || !before.trim_start_matches('/').is_empty()
+                }),
+                PatternPart::Placeholder(_) => None,
+            };
+            let whole_segment = matches!(&pattern_parts[i + 1],
+                PatternPart::Literal(after) if after.is_empty() || after.starts_with('/'));
+            let Some(before) = before.filter(|_| whole_segment) else {
+                return Err(syn::Error::new(
+                    url_pattern.span(),
+                    format!("optional placeholder `{{{placeholder}}}` has to be a whole path segment, and can't be the first one"),
+                ));
+            };
+            segments.pop();
+            segments.push(quote! { path.push_str(#before); });
+            match_parts.pop();
+            match_parts.push(match_literal(i - 1, before));
+            match_parts.push(quote! { #krate::PatternPart::OptionalSegment });
+        } else {
+            match_parts.push(quote! { #krate::PatternPart::Placeholder });
+        }
+        placeholder_fields.push(field);
+        describe_path.push_str(&format!("{{{}}}", field.name));
+
+        let raw = field.attrs.raw;
+        let push_value = |value: proc_macro2::TokenStream| {
+            if raw {
+                quote! { let _ = std::fmt::Write::write_fmt(&mut path, format_args!("{}", #value)); }
+            } else {
+                quote! { #krate::push_path_segment(&mut path, #value); }
+            }
+        };
+
+        let binding = &field.binding;
+        let value = if optional { quote! { value } } else { quote! { #binding } };
+        let push = if is_vec_type(path_param_type(field)) {
+            let separator = match field.attrs.vec.unwrap_or(VecStyle::Comma).separator() {
+                Some(separator) => separator,
+                None => {
+                    return Err(syn::Error::new(
+                        field_ident.span(),
+                        format!("`{field_ident}` is a path parameter, only `comma` and `pipe` are supported"),
+                    ))
+                }
+            };
+            let push_element = push_value(quote! { element });
+            quote! {
+                for (i, element) in #value.iter().enumerate() {
+                    if i > 0 {
+                        path.push_str(#separator);
                     }
-                    url.push_str(#rest);
-                },
-                _ => quote! {
-                    url.push_str(&self.#field_ident.to_string());
-                    url.push_str(#rest);
-                },
+                    #push_element
+                }
             }
-        })
-        .collect();
+        } else {
+            push_value(value)
+        };
+        segments.push(if optional {
+            quote! {
+                if let Some(value) = #binding {
+                    path.push('/');
+                    #push
+                }
+            }
+        } else {
+            push
+        });
+    }
 
-    // Check for query parameters
-    let has_query_params = field_names
+    if let Some(field) = fields
         .iter()
-        .zip(field_types.iter())
-        .any(|(name, ty)| !is_path_param(&name.to_string(), &url_pattern) && !is_option_type(ty));
+        .find(|f| f.attrs.placement == Placement::Path && !path_fields.contains(f.ident))
+    {
+        return Err(syn::Error::new(
+            field.ident.span(),
+            format!(
+                "`{}` is marked as `path` but the URL pattern has no `{{{}}}` placeholder",
+                field.ident, field.name
+            ),
+        ));
+    }
 
-    let has_optional_params = field_names
+    let query_fields: Vec<_> = fields
         .iter()
-        .zip(field_types.iter())
-        .any(|(name, ty)| {
-            !is_path_param(&name.to_string(), &url_pattern)
-                && (is_option_type(ty) || is_vec_type(ty))
-        });
+        .filter(|f| !f.attrs.skip && !f.attrs.body && !path_fields.contains(f.ident))
+        .collect();
+    if let Some(field) = fields
+        .iter()
+        .find(|f| f.attrs.optional && !path_fields.contains(f.ident))
+    {
+        return Err(syn::Error::new(
+            field.ident.span(),
+            format!("`{}` is marked as `optional` but isn't a path parameter", field.ident),
+        ));
+    }
 
-    // Generate query parameter handling code
-    let query_additions = if has_query_params || has_optional_params {
-        let mut optional_checks = Vec::new();
-        let mut required_additions = Vec::new();
-        let mut has_required = false;
-
-        for (name, ty) in field_names.iter().zip(field_types.iter()) {
-            if !is_path_param(&name.to_string(), &url_pattern) {
-                if is_option_type(ty) {
-                    if let Some(inner_ty) = get_inner_type(ty) {
-                        if is_vec_type(inner_ty) {
-                            optional_checks.push(quote! {
-                                if let Some(values) = &self.#name {
-                                    if !values.is_empty() {
-                                        for value in values {
-                                            parsed_url.query_pairs_mut().append_pair(
-                                                stringify!(#name),
-                                                &value.to_string()
-                                            );
-                                        }
-                                    }
-                                }
-                            });
-                        } else {
-                            optional_checks.push(quote! {
-                                if let Some(value) = &self.#name {
-                                    parsed_url.query_pairs_mut().append_pair(
-                                        stringify!(#name),
-                                        &value.to_string()
-                                    );
-                                }
-                            });
-                        }
-                    }
-                } else if is_vec_type(ty) {
-                    optional_checks.push(quote! {
-                        if !self.#name.is_empty() {
-                            for value in &self.#name {
-                                parsed_url.query_pairs_mut().append_pair(
-                                    stringify!(#name),
-                                    &value.to_string()
-                                );
-                            }
+    // Generate query parameter handling code, optional parameters come first
+    let mut optional_additions = Vec::new();
+    let mut required_additions = Vec::new();
+    for field in &query_fields {
+        let name = &field.binding;
+        let key = &field.name;
+        let ty = &field.field.ty;
+        let vec_style = field.attrs.vec.unwrap_or(VecStyle::Repeat);
+        if is_option_type(ty) {
+            if let Some(inner_ty) = get_inner_type(ty) {
+                if is_vec_type(inner_ty) {
+                    let append = append_vec(key, vec_style);
+                    optional_additions.push(quote! {
+                        if let Some(values) = #name {
+                            #append
                         }
                     });
                 } else {
-                    has_required = true;
-                    required_additions.push(quote! {
-                        parsed_url.query_pairs_mut().append_pair(
-                            stringify!(#name),
-                            &self.#name.to_string()
-                        );
+                    optional_additions.push(quote! {
+                        if let Some(value) = #name {
+                            #krate::push_query_pair(&mut query, #key, value);
+                        }
                     });
                 }
             }
+        } else if is_vec_type(ty) {
+            let append = append_vec(key, vec_style);
+            optional_additions.push(quote! {
+                let values = #name;
+                #append
+            });
+        } else {
+            required_additions.push(quote! {
+                #krate::push_query_pair(&mut query, #key, #name);
+            });
+        }
+    }
+
+    let parsed_url = quote! {
+        #krate::join_base_url(base_url.into_base_url()?, &path)
+    };
+    let build_url = if query_fields.is_empty() {
+        quote! { Ok(#parsed_url) }
+    } else {
+        quote! {
+            let mut parsed_url = #parsed_url;
+            let mut query = String::new();
+            #(#optional_additions)*
+            #(#required_additions)*
+            if !query.is_empty() {
+                parsed_url.set_query(Some(&query));
+            }
+
+            Ok(parsed_url)
+        }
+    };
+
+    let url_fields: Vec<_> = placeholder_fields.iter().chain(&query_fields).copied().collect();
+    let bindings = url_fields.iter().map(|f| {
+        let ident = f.ident;
+        let binding = &f.binding;
+        quote! { #ident: #binding }
+    });
+    let destructure = quote! { { #(#bindings,)* .. } };
+
+    let as_url = quote! {
+        let mut path = String::new();
+        #(#segments)*
+
+        #build_url
+    };
+
+    let from_url = from_url_body(constructor, &match_parts, &placeholder_fields, &fields, &query_fields);
+
+    if !describe_path.starts_with('/') {
+        describe_path.insert(0, '/');
+    }
+    let describe_params = placeholder_fields
+        .iter()
+        .map(|field| {
+            let vec = is_vec_type(path_param_type(field))
+                .then(|| field.attrs.vec.unwrap_or(VecStyle::Comma));
+            param_descriptor(field, &field.name, quote! { Path }, !field.attrs.optional, vec)
+        })
+        .chain(query_fields.iter().map(|field| {
+            let ty = &field.field.ty;
+            let inner = get_inner_type(ty).filter(|_| is_option_type(ty)).unwrap_or(ty);
+            if !is_vec_type(inner) {
+                return param_descriptor(field, &field.name, quote! { Query }, !is_option_type(ty), None);
+            }
+            // `Vec`s may be empty, in which case they're left out
+            let vec = field.attrs.vec.unwrap_or(VecStyle::Repeat);
+            param_descriptor(field, &vec.key(&field.name), quote! { Query }, false, Some(vec))
+        }))
+        .collect();
+
+    Ok(UrlExpansion {
+        pattern: url_pattern,
+        destructure,
+        as_url,
+        from_url,
+        url_types: url_fields.iter().map(|f| value_type(&f.field.ty)).collect(),
+        default_types: fields
+            .iter()
+            .filter(|f| f.attrs.skip || f.attrs.body)
+            .map(|f| &f.field.ty)
+            .collect(),
+        describe_path,
+        describe_params,
+        fields,
+    })
+}
+
+/// The name of `ty` in a `ParamDescriptor`, without references and lifetimes, e.g. `str` for
+/// `&'a str` and `Cow<str>` for `Cow<'a, str>`.
+fn type_name(ty: &Type) -> String {
+    match ty {
+        Type::Reference(reference) => type_name(&reference.elem),
+        Type::Paren(paren) => type_name(&paren.elem),
+        Type::Group(group) => type_name(&group.elem),
+        Type::Path(path) if path.qself.is_none() => {
+            let segments: Vec<_> = path
+                .path
+                .segments
+                .iter()
+                .map(|segment| {
+                    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+                        return segment.ident.to_string();
+                    };
+                    let arguments: Vec<_> = arguments
+                        .args
+                        .iter()
+                        .filter_map(|argument| match argument {
+                            GenericArgument::Type(ty) => Some(type_name(ty)),
+                            _ => None,
+                        })
+                        .collect();
+                    if arguments.is_empty() {
+                        segment.ident.to_string()
+                    } else {
+                        format!("{}<{}>", segment.ident, arguments.join(", "))
+                    }
+                })
+                .collect();
+            segments.join("::")
+        }
+        ty => ty.to_token_stream().to_string().replace(' ', ""),
+    }
+}
+
+/// Generates the `ParamDescriptor` of the parameter `name` read from `field`.
+fn param_descriptor(
+    field: &UrlField,
+    name: &str,
+    location: proc_macro2::TokenStream,
+    required: bool,
+    vec: Option<VecStyle>,
+) -> proc_macro2::TokenStream {
+    let krate = runtime_crate();
+    let type_name = type_name(value_type(&field.field.ty));
+    let vec = match vec {
+        Some(style) => {
+            let style = match style {
+                VecStyle::Repeat => quote! { Repeat },
+                VecStyle::Comma => quote! { Comma },
+                VecStyle::Pipe => quote! { Pipe },
+                VecStyle::Brackets => quote! { Brackets },
+            };
+            quote! { Some(#krate::VecStyle::#style) }
+        }
+        None => quote! { None },
+    };
+    let doc = option_str(doc_comment(&field.field.attrs));
+    quote! {
+        #krate::ParamDescriptor {
+            name: #name,
+            location: #krate::ParamLocation::#location,
+            type_name: #type_name,
+            required: #required,
+            vec: #vec,
+            doc: #doc,
+        }
+    }
+}
+
+/// Generates an `Option<&'static str>`.
+fn option_str(value: Option<String>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
+
+/// Generates the `DescribeUrl` impl if the struct has `#[url(describe)]`.
+fn describe_impl(
+    input: &DeriveInput,
+    container: &ContainerAttrs,
+    expansion: &UrlExpansion,
+) -> proc_macro2::TokenStream {
+    if !container.describe {
+        return quote! {};
+    }
+
+    let krate = runtime_crate();
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let path = &expansion.describe_path;
+    let method = match &container.method {
+        Some(method) => quote! { Some(#krate::Method::#method) },
+        None => quote! { None },
+    };
+    let doc = option_str(doc_comment(&input.attrs));
+    let params = &expansion.describe_params;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics #krate::DescribeUrl for #name #ty_generics #where_clause {
+            const DESCRIPTOR: #krate::UrlDescriptor = #krate::UrlDescriptor {
+                name: #name_str,
+                path: #path,
+                method: #method,
+                doc: #doc,
+                params: &[#(#params),*],
+            };
+        }
+    }
+}
+
+/// Generates the code parsing the path parameter `segment` into the field.
+fn parse_path_param(field: &UrlField) -> proc_macro2::TokenStream {
+    let krate = runtime_crate();
+    let name = &field.name;
+    let decode = if field.attrs.raw {
+        quote! { Some(std::borrow::Cow::Borrowed(segment)) }
+    } else {
+        quote! { #krate::decode_path_segment(segment) }
+    };
+    quote! {
+        match #decode {
+            Some(decoded) => decoded.parse().map_err(|e| #krate::FromUrlError::InvalidPathParam {
+                field: #name.to_string(),
+                value: segment.to_string(),
+                source: Some(Into::into(e)),
+            }),
+            None => Err(#krate::FromUrlError::InvalidPathParam {
+                field: #name.to_string(),
+                value: segment.to_string(),
+                source: None,
+            }),
         }
+    }
+}
 
-        // Combine required and optional parameters
-        if has_required {
+/// Generates the inverse of `as_url` for the fields of a struct or of an enum variant: if the
+/// path of `url` matches the pattern, the value is parsed and returned.
+///
+/// Path parameters are percent-decoded unless the field is `raw`, query parameters go
+/// through the same extraction as `TryFromMap`, and skipped and body fields are defaulted.
+fn from_url_body(
+    constructor: proc_macro2::TokenStream,
+    match_parts: &[proc_macro2::TokenStream],
+    placeholder_fields: &[&UrlField],
+    fields: &[UrlField],
+    query_fields: &[&UrlField],
+) -> proc_macro2::TokenStream {
+    let krate = runtime_crate();
+    let path_extractions = placeholder_fields.iter().enumerate().map(|(i, field)| {
+        let binding = &field.binding;
+        let parse = parse_path_param(field);
+        let parse = if is_vec_type(path_param_type(field)) {
+            let separator = field.attrs.vec.unwrap_or(VecStyle::Comma).separator();
             quote! {
-                #(#optional_checks)*
-                #(#required_additions)*
+                if segment.is_empty() {
+                    Vec::new()
+                } else {
+                    segment
+                        .split(#separator)
+                        .map(|segment| #parse)
+                        .collect::<Result<Vec<_>, _>>()?
+                }
             }
         } else {
+            quote! { #parse? }
+        };
+        if field.attrs.optional {
             quote! {
-                #(#optional_checks)*
-                if parsed_url.query().map_or(true, str::is_empty) {
-                    parsed_url.set_query(None);
-                }
+                let #binding = match path_values[#i] {
+                    Some(segment) => Some(#parse),
+                    None => None,
+                };
+            }
+        } else {
+            quote! {
+                let segment = path_values[#i].unwrap_or_default();
+                let #binding = #parse;
             }
         }
+    });
+    let path_values = if placeholder_fields.is_empty() {
+        quote! { _ }
     } else {
+        quote! { path_values }
+    };
+
+    let query = if query_fields.is_empty() {
         quote! {}
+    } else {
+        // A missing `Vec` is an empty one, as `as_url` leaves empty `Vec`s out of the query
+        let empty_vecs = query_fields
+            .iter()
+            .filter(|f| is_vec_type(&f.field.ty))
+            .map(|f| {
+                let key = f.attrs.vec.unwrap_or(VecStyle::Repeat).key(&f.name);
+                quote! { value.entry(std::borrow::Cow::Borrowed(#key)).or_default(); }
+            });
+        let map_fields: Vec<_> = query_fields
+            .iter()
+            .map(|f| MapField {
+                binding: &f.binding,
+                key: &f.name,
+                ty: &f.field.ty,
+                vec_style: f.attrs.vec.unwrap_or(VecStyle::Repeat),
+                default: None,
+                parse_with: None,
+                single: false,
+            })
+            .collect();
+        let extraction = fields_extraction(&map_fields, false);
+        let bindings: Vec<_> = map_fields.iter().map(|f| f.binding).collect();
+        quote! {
+            let mut value = std::collections::HashMap::<std::borrow::Cow<str>, Vec<_>>::new();
+            for (key, pair_value) in url.query_pairs() {
+                value.entry(key).or_default().push(pair_value);
+            }
+            #(#empty_vecs)*
+            let mut errors = Vec::new();
+            #extraction
+            let (#(Some(#bindings),)*) = (#(#bindings,)*) else {
+                return Err(#krate::TryFromMapErrors::from(errors).into());
+            };
+        }
     };
 
-    // Generate the final implementation
-    let expanded = quote! {
-        impl AsUrl for #name {
-            fn as_url(&self, base_url: &str) -> Result<url::Url, url::ParseError> {
-                let mut url = String::from(base_url);
-                #(#segments)*
+    let field_values = fields.iter().map(|f| {
+        let ident = f.ident;
+        if f.attrs.skip || f.attrs.body {
+            quote! { #ident: Default::default() }
+        } else {
+            let binding = &f.binding;
+            quote! { #ident: #binding }
+        }
+    });
+
+    quote! {
+        if let Some(#path_values) = #krate::match_path(url.path(), &[#(#match_parts),*]) {
+            #query
+            #(#path_extractions)*
+
+            return Ok(#constructor {
+                #(#field_values),*
+            });
+        }
+    }
+}
+
+/// Generates the `FromUrl` and `TryFrom<&Url>` impls, trying the patterns in order.
+fn from_url_impl(
+    name: &syn::Ident,
+    generics: &Generics,
+    expansions: &[UrlExpansion],
+) -> proc_macro2::TokenStream {
+    let krate = runtime_crate();
+    let generics = with_bounds(
+        generics,
+        expansions.iter().flat_map(|e| e.url_types.iter().copied()),
+        quote! { std::str::FromStr },
+    );
+    let generics = with_bounds(
+        &generics,
+        &parse_error_types(expansions.iter().flat_map(|e| e.url_types.iter().copied())),
+        parse_error_bound(),
+    );
+    let generics = with_bounds(
+        &generics,
+        expansions.iter().flat_map(|e| e.default_types.iter().copied()),
+        quote! { Default },
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let bodies = expansions.iter().map(|e| &e.from_url);
+    let patterns = expansions
+        .iter()
+        .map(|e| e.pattern.value())
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    quote! {
+        impl #impl_generics #krate::FromUrl for #name #ty_generics #where_clause {
+            fn from_url(url: &#krate::url::Url) -> Result<Self, #krate::FromUrlError> {
+                #(#bodies)*
+
+                Err(#krate::FromUrlError::PathMismatch {
+                    pattern: #patterns,
+                    path: url.path().to_string(),
+                })
+            }
+        }
+
+        impl #impl_generics TryFrom<&#krate::url::Url> for #name #ty_generics #where_clause {
+            type Error = #krate::FromUrlError;
+
+            fn try_from(url: &#krate::url::Url) -> Result<Self, Self::Error> {
+                <Self as #krate::FromUrl>::from_url(url)
+            }
+        }
+    }
+}
+
+/// Generates the `AtlasRequest` impl if the struct has `#[url(method = "...")]`.
+fn request_impl(
+    name: &syn::Ident,
+    generics: &Generics,
+    container: &ContainerAttrs,
+    fields: &[UrlField],
+) -> syn::Result<proc_macro2::TokenStream> {
+    let krate = runtime_crate();
+    let mut body_fields = fields.iter().filter(|f| f.attrs.body);
+    let body_field = body_fields.next();
+    if let Some(field) = body_fields.next() {
+        return Err(syn::Error::new(
+            field.ident.span(),
+            "only one field can be marked as `body`",
+        ));
+    }
 
-                let mut parsed_url = url::Url::parse(&url)?;
-                #query_additions
+    let Some(method) = &container.method else {
+        if let Some(field) = body_field {
+            return Err(syn::Error::new(
+                field.ident.span(),
+                "a `body` field requires #[url(method = \"...\")]",
+            ));
+        }
+        if container.version.is_some() || container.response.is_some() {
+            return Err(syn::Error::new(
+                name.span(),
+                "`version` and `response` require #[url(method = \"...\")]",
+            ));
+        }
+        return Ok(quote! {});
+    };
 
-                Ok(parsed_url)
+    let version = match &container.version {
+        Some(version) => quote! { Some(#version) },
+        None => quote! { None },
+    };
+    let unit: Type = parse_quote! { () };
+    let response = container.response.as_ref().unwrap_or(&unit);
+    let (body_type, body) = match body_field {
+        Some(field) => {
+            let ident = field.ident;
+            let ty = &field.field.ty;
+            match get_inner_type(ty).filter(|_| is_option_type(ty)) {
+                Some(inner) => (inner, quote! { self.#ident.as_ref() }),
+                None => (ty, quote! { Some(&self.#ident) }),
             }
         }
+        None => (&unit, quote! { None }),
     };
 
-    TokenStream::from(expanded)
+    let generics = with_bounds(generics, [body_type], quote! { #krate::__private::Serialize });
+    let generics = with_bounds(&generics, [response], quote! { #krate::__private::DeserializeOwned });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #krate::AtlasRequest for #name #ty_generics #where_clause {
+            const METHOD: #krate::Method = #krate::Method::#method;
+            const VERSION: Option<&'static str> = #version;
+
+            type Body = #body_type;
+            type Response = #response;
+
+            fn body(&self) -> Option<&Self::Body> {
+                #body
+            }
+        }
+    })
 }
@@ -0,0 +1,232 @@
+use atlas_core::AtlasURL;
+use atlas_derive_core::{DescribeUrl, Method, ParamDescriptor, ParamLocation, VecStyle};
+use serde_json::json;
+
+/// Get the measurements of a process.
+///
+/// Returns the data points of each metric.
+#[derive(AtlasURL)]
+#[url(
+    "api/atlas/v2/groups/{group_id}/processes/{process_id}/measurements/{kind}",
+    rename_all = "camelCase",
+    method = "GET",
+    describe
+)]
+#[allow(dead_code)]
+struct Measurements {
+    /// The project of the process.
+    group_id: String,
+    #[url(rename = "hostPort")]
+    process_id: String,
+    #[url(optional)]
+    kind: Option<String>,
+    /// The duration between two data points.
+    granularity: String,
+    #[url(rename = "m")]
+    metrics: Vec<String>,
+    #[url(vec = "comma")]
+    tags: Option<Vec<String>>,
+    #[url(vec = "brackets")]
+    hosts: Vec<String>,
+    items_per_page: Option<u32>,
+    include_count: bool,
+    #[url(skip)]
+    cache_key: String,
+}
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}", describe)]
+#[allow(dead_code)]
+struct Group {
+    group_id: String,
+    #[url(vec = "pipe")]
+    fields: Vec<String>,
+}
+
+#[test]
+fn test_descriptor() {
+    let descriptor = Measurements::DESCRIPTOR;
+    assert_eq!(descriptor.name, "Measurements");
+    assert_eq!(
+        descriptor.path,
+        "/api/atlas/v2/groups/{groupId}/processes/{hostPort}/measurements/{kind}"
+    );
+    assert_eq!(descriptor.method, Some(Method::Get));
+    assert_eq!(
+        descriptor.doc,
+        Some("Get the measurements of a process.\n\nReturns the data points of each metric.")
+    );
+
+    let names: Vec<_> = descriptor.params.iter().map(|param| param.name).collect();
+    assert_eq!(
+        names,
+        [
+            "groupId",
+            "hostPort",
+            "kind",
+            "granularity",
+            "m",
+            "tags",
+            "hosts[]",
+            "itemsPerPage",
+            "includeCount",
+        ]
+    );
+    assert_eq!(
+        descriptor.param("groupId"),
+        Some(&ParamDescriptor {
+            name: "groupId",
+            location: ParamLocation::Path,
+            type_name: "String",
+            required: true,
+            vec: None,
+            doc: Some("The project of the process."),
+        })
+    );
+    assert!(!descriptor.param("kind").unwrap().required);
+    assert_eq!(
+        descriptor.param("tags"),
+        Some(&ParamDescriptor {
+            name: "tags",
+            location: ParamLocation::Query,
+            type_name: "String",
+            required: false,
+            vec: Some(VecStyle::Comma),
+            doc: None,
+        })
+    );
+    assert_eq!(descriptor.param("hosts[]").unwrap().vec, Some(VecStyle::Brackets));
+    assert_eq!(descriptor.param("itemsPerPage").unwrap().type_name, "u32");
+    assert!(descriptor.param("includeCount").unwrap().required);
+    assert!(descriptor.param("cacheKey").is_none());
+}
+
+#[test]
+fn test_openapi_operation() {
+    assert_eq!(
+        Measurements::DESCRIPTOR.openapi_path_item(),
+        json!({
+            "get": {
+                "operationId": "measurements",
+                "summary": "Get the measurements of a process.",
+                "description": "Get the measurements of a process.\n\nReturns the data points of each metric.",
+                "parameters": [
+                    {
+                        "name": "groupId",
+                        "in": "path",
+                        "required": true,
+                        "description": "The project of the process.",
+                        "schema": { "type": "string" },
+                    },
+                    { "name": "hostPort", "in": "path", "required": true, "schema": { "type": "string" } },
+                    { "name": "kind", "in": "path", "required": true, "schema": { "type": "string" } },
+                    {
+                        "name": "granularity",
+                        "in": "query",
+                        "required": true,
+                        "description": "The duration between two data points.",
+                        "schema": { "type": "string" },
+                    },
+                    {
+                        "name": "m",
+                        "in": "query",
+                        "required": false,
+                        "schema": { "type": "array", "items": { "type": "string" } },
+                        "style": "form",
+                        "explode": true,
+                    },
+                    {
+                        "name": "tags",
+                        "in": "query",
+                        "required": false,
+                        "schema": { "type": "array", "items": { "type": "string" } },
+                        "style": "form",
+                        "explode": false,
+                    },
+                    {
+                        "name": "hosts[]",
+                        "in": "query",
+                        "required": false,
+                        "schema": { "type": "array", "items": { "type": "string" } },
+                        "style": "form",
+                        "explode": true,
+                    },
+                    {
+                        "name": "itemsPerPage",
+                        "in": "query",
+                        "required": false,
+                        "schema": { "type": "integer", "format": "int64" },
+                    },
+                    {
+                        "name": "includeCount",
+                        "in": "query",
+                        "required": true,
+                        "schema": { "type": "boolean" },
+                    },
+                ],
+            },
+        })
+    );
+}
+
+#[test]
+fn test_openapi_brackets() {
+    let expected = json!({
+        "name": "hosts[]",
+        "in": "query",
+        "required": false,
+        "schema": { "type": "array", "items": { "type": "string" } },
+        "style": "form",
+        "explode": true,
+    });
+    let hosts = Measurements::DESCRIPTOR.param("hosts[]").unwrap();
+    assert_eq!(hosts.openapi_parameter(), expected);
+
+    // The key gets its brackets even if the descriptor is written by hand without them
+    let hosts = ParamDescriptor {
+        name: "hosts",
+        ..*hosts
+    };
+    assert_eq!(hosts.openapi_parameter(), expected);
+}
+
+#[test]
+fn test_openapi_without_method() {
+    assert_eq!(Group::DESCRIPTOR.method, None);
+    assert_eq!(
+        Group::DESCRIPTOR.openapi_path_item(),
+        json!({
+            "parameters": [
+                { "name": "group_id", "in": "path", "required": true, "schema": { "type": "string" } },
+                {
+                    "name": "fields",
+                    "in": "query",
+                    "required": false,
+                    "schema": { "type": "array", "items": { "type": "string" } },
+                    "style": "pipeDelimited",
+                    "explode": false,
+                },
+            ],
+        })
+    );
+}
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters", describe)]
+#[allow(dead_code)]
+struct Clusters<'a> {
+    group_id: &'a str,
+    state: Option<&'a str>,
+    #[url(vec = "comma")]
+    fields: Vec<&'a str>,
+}
+
+#[test]
+fn test_borrowed_fields() {
+    let type_names: Vec<_> = Clusters::DESCRIPTOR.params.iter().map(|param| param.type_name).collect();
+    assert_eq!(type_names, ["str", "str", "str"]);
+    assert_eq!(
+        Clusters::DESCRIPTOR.param("state").unwrap().openapi_parameter(),
+        json!({ "name": "state", "in": "query", "required": false, "schema": { "type": "string" } })
+    );
+}
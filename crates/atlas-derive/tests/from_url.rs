@@ -0,0 +1,205 @@
+use atlas_core::AtlasURL;
+use atlas_derive_core::{AsUrl, FromUrl, FromUrlError, TryFromMapError};
+use proptest::prelude::*;
+use url::Url;
+
+#[derive(AtlasURL, Debug, Clone, PartialEq)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters/{cluster_name}", rename_all = "camelCase")]
+struct Cluster {
+    group_id: String,
+    cluster_name: String,
+    page_num: u32,
+    items_per_page: Option<u32>,
+    include_count: Option<bool>,
+}
+
+#[derive(AtlasURL, Debug, Clone, PartialEq)]
+#[url("api/atlas/v2/groups/{group_id}/processes/{hosts}/measurements")]
+struct Measurements {
+    group_id: String,
+    #[url(vec = "pipe")]
+    hosts: Vec<String>,
+    #[url(rename = "m")]
+    metrics: Vec<String>,
+    #[url(vec = "comma")]
+    granularity: Option<Vec<String>>,
+    #[url(vec = "brackets")]
+    tags: Vec<u32>,
+    #[url(skip)]
+    cache_key: String,
+}
+
+#[derive(AtlasURL, Debug, Clone, PartialEq)]
+#[url("/api/atlas/v2/groups/{group_id}/files/{name}")]
+struct File {
+    group_id: String,
+    #[url(raw)]
+    name: String,
+}
+
+#[derive(AtlasURL, Debug, Clone, PartialEq)]
+#[url("/api/atlas/v2/groups/{group_id}/snapshots/{snapshot}")]
+struct Snapshot {
+    group_id: String,
+    snapshot: u32,
+}
+
+fn url(url: &str) -> Url {
+    Url::parse(url).unwrap()
+}
+
+#[test]
+fn test_from_url() {
+    let cluster = Cluster::from_url(&url(
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/clusters/Cluster%200?pageNum=2&itemsPerPage=100&extra=ignored",
+    ))
+    .unwrap();
+    assert_eq!(
+        cluster,
+        Cluster {
+            group_id: "1".to_string(),
+            cluster_name: "Cluster 0".to_string(),
+            page_num: 2,
+            items_per_page: Some(100),
+            include_count: None,
+        }
+    );
+
+    // The path of the base URL is skipped
+    let cluster: Cluster = (&url(
+        "https://ops.example.com/opsmanager/api/atlas/v2/groups/1/clusters/Cluster0?pageNum=1",
+    ))
+        .try_into()
+        .unwrap();
+    assert_eq!(cluster.cluster_name, "Cluster0");
+}
+
+#[test]
+fn test_from_url_vecs_raw_and_skip() {
+    let measurements = Measurements::from_url(&url(
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/processes/a|b%2Fc/measurements?m=CPU&m=DISK&granularity=PT1M,PT5M&tags[]=1&tags[]=2",
+    ))
+    .unwrap();
+    assert_eq!(measurements.hosts, ["a", "b/c"]);
+    assert_eq!(measurements.metrics, ["CPU", "DISK"]);
+    assert_eq!(
+        measurements.granularity,
+        Some(vec!["PT1M".to_string(), "PT5M".to_string()])
+    );
+    assert_eq!(measurements.tags, [1, 2]);
+    assert_eq!(measurements.cache_key, "");
+
+    // Empty `Vec`s are left out of the query
+    let measurements = Measurements::from_url(&url(
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/processes/a/measurements",
+    ))
+    .unwrap();
+    assert!(measurements.metrics.is_empty());
+    assert!(measurements.tags.is_empty());
+    assert_eq!(measurements.granularity, None);
+
+    let file = File::from_url(&url(
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/files/logs%2Fmongod.gz",
+    ))
+    .unwrap();
+    assert_eq!(file.name, "logs%2Fmongod.gz");
+}
+
+#[test]
+fn test_from_url_errors() {
+    let result = Cluster::from_url(&url("https://cloud.mongodb.com/api/atlas/v2/groups/1"));
+    assert!(matches!(result, Err(FromUrlError::PathMismatch { pattern, path })
+        if pattern == "/api/atlas/v2/groups/{group_id}/clusters/{cluster_name}"
+            && path == "/api/atlas/v2/groups/1"));
+
+    let result = Cluster::from_url(&url(
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/clusters/Cluster0/restoreJobs?pageNum=1",
+    ));
+    assert!(matches!(result, Err(FromUrlError::PathMismatch { .. })));
+
+    let result = Cluster::from_url(&url(
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/clusters/Cluster0",
+    ));
+    assert!(matches!(result, Err(FromUrlError::Query(errors))
+        if matches!(errors.errors(), [TryFromMapError::MissingField(field)] if field == "pageNum")));
+
+    let result = Measurements::from_url(&url(
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/processes/a/measurements?tags[]=x",
+    ));
+    assert!(matches!(result, Err(FromUrlError::Query(errors))
+        if matches!(errors.errors(), [TryFromMapError::ParseError { field, value, .. }]
+            if field == "tags[]" && value == "x")));
+
+    let result = File::from_url(&url("https://cloud.mongodb.com/api/atlas/v2/groups/1/files/%FF"));
+    assert!(result.is_ok());
+    let result = Cluster::from_url(&url(
+        "https://cloud.mongodb.com/api/atlas/v2/groups/%FF/clusters/Cluster0?pageNum=1",
+    ));
+    assert!(matches!(result, Err(FromUrlError::InvalidPathParam { field, value, source: None })
+        if field == "groupId" && value == "%FF"));
+
+    let error = Snapshot::from_url(&url(
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/snapshots/latest",
+    ))
+    .unwrap_err();
+    assert!(matches!(&error, FromUrlError::InvalidPathParam { field, value, source: Some(_) }
+        if field == "snapshot" && value == "latest"));
+    assert_eq!(
+        std::error::Error::source(&error).unwrap().to_string(),
+        "invalid digit found in string"
+    );
+}
+
+fn is_dot_segment(s: &String) -> bool {
+    s == "." || s == ".."
+}
+
+/// Any string `set_path` doesn't normalize away, i.e. not a `.` or `..` segment.
+fn path_param() -> impl Strategy<Value = String> {
+    any::<String>().prop_filter("dot segment", |s| !is_dot_segment(s))
+}
+
+/// Non-empty values which don't contain the separators of joined `Vec`s.
+fn element() -> impl Strategy<Value = String> {
+    "[^,|]{1,8}".prop_filter("dot segment", |s| !is_dot_segment(s))
+}
+
+proptest! {
+    #[test]
+    fn round_trip_cluster(
+        group_id in path_param(),
+        cluster_name in path_param(),
+        page_num: u32,
+        items_per_page: Option<u32>,
+        include_count: Option<bool>,
+        base_url in prop::sample::select(vec![
+            "https://cloud.mongodb.com",
+            "https://ops.example.com/opsmanager/",
+            "http://localhost:8080/a/b",
+        ]),
+    ) {
+        let cluster = Cluster { group_id, cluster_name, page_num, items_per_page, include_count };
+        let url = cluster.as_url(base_url).unwrap();
+        prop_assert_eq!(Cluster::from_url(&url).unwrap(), cluster);
+    }
+
+    #[test]
+    fn round_trip_measurements(
+        group_id in path_param(),
+        hosts in prop::collection::vec(element(), 0..4),
+        metrics in prop::collection::vec(any::<String>(), 0..4),
+        granularity in prop::option::of(prop::collection::vec(element(), 1..4)),
+        tags in prop::collection::vec(any::<u32>(), 0..4),
+    ) {
+        let measurements = Measurements {
+            group_id,
+            hosts,
+            metrics,
+            granularity,
+            tags,
+            cache_key: String::new(),
+        };
+        let url = measurements.as_url("https://cloud.mongodb.com").unwrap();
+        prop_assert_eq!(Measurements::from_url(&url).unwrap(), measurements);
+    }
+}
@@ -0,0 +1,194 @@
+use atlas_core::AtlasURL;
+use atlas_derive::{MapArgs, MapEnum, TryFromMap};
+use atlas_derive_core::{AsUrl, MapArgs, TryFromMapError};
+use clap::Command;
+
+#[derive(MapEnum, Debug, Clone, PartialEq)]
+#[map(rename_all = "UPPERCASE")]
+enum Granularity {
+    Minute,
+    Hour,
+}
+
+#[derive(TryFromMap, MapArgs, Debug, Clone, Default, PartialEq)]
+#[map(rename_all = "camelCase")]
+struct Page {
+    /// The page to return.
+    #[map(default)]
+    page_num: u32,
+    items_per_page: Option<u32>,
+}
+
+#[derive(TryFromMap, MapArgs, AtlasURL, Debug, Clone, PartialEq)]
+#[url("/api/atlas/v2/groups/{group_id}/processes/{process_id}/measurements", rename_all = "camelCase")]
+#[map(rename_all = "camelCase")]
+struct Measurements {
+    /// The project of the process.
+    group_id: String,
+    process_id: String,
+    /// The duration between two data points.
+    ///
+    /// Finer granularities return more data points.
+    granularity: Granularity,
+    /// The measurements to return, all of them if there are none.
+    #[map(rename = "m", default)]
+    #[url(rename = "m")]
+    metrics: Vec<String>,
+    /// Whether to include the count of the results.
+    include_count: bool,
+    #[map(flatten)]
+    #[url(skip)]
+    page: Page,
+}
+
+fn command() -> Command {
+    Measurements::augment_command(Command::new("measurements"))
+}
+
+fn parse(args: &[&str]) -> Measurements {
+    let matches = command().try_get_matches_from(args).unwrap();
+    Measurements::from_arg_matches(&matches).unwrap()
+}
+
+#[test]
+fn test_args() {
+    let measurements = parse(&[
+        "measurements",
+        "--group-id",
+        "1",
+        "--process-id",
+        "host:27017",
+        "--granularity",
+        "minute",
+        "--metrics",
+        "CONNECTIONS",
+        "--metrics",
+        "OPCOUNTER_CMD",
+        "--include-count",
+        "--items-per-page",
+        "10",
+    ]);
+    assert_eq!(
+        measurements,
+        Measurements {
+            group_id: "1".to_string(),
+            process_id: "host:27017".to_string(),
+            granularity: Granularity::Minute,
+            metrics: vec!["CONNECTIONS".to_string(), "OPCOUNTER_CMD".to_string()],
+            include_count: true,
+            page: Page {
+                page_num: 0,
+                items_per_page: Some(10),
+            },
+        }
+    );
+    assert_eq!(
+        measurements.as_url("https://cloud.mongodb.com").unwrap().as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/processes/host:27017/measurements?m=CONNECTIONS&m=OPCOUNTER_CMD&granularity=MINUTE&includeCount=true"
+    );
+}
+
+#[test]
+fn test_defaults() {
+    let measurements = parse(&[
+        "measurements",
+        "--group-id=1",
+        "--process-id=host",
+        "--granularity=HOUR",
+    ]);
+    assert_eq!(measurements.metrics, Vec::<String>::new());
+    assert!(!measurements.include_count);
+    assert_eq!(
+        measurements.page,
+        Page {
+            page_num: 0,
+            items_per_page: None,
+        }
+    );
+}
+
+#[test]
+fn test_required_args() {
+    let error = command()
+        .try_get_matches_from(["measurements", "--group-id", "1"])
+        .unwrap_err();
+    assert_eq!(error.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+}
+
+#[test]
+fn test_invalid_values() {
+    let matches = command()
+        .try_get_matches_from([
+            "measurements",
+            "--group-id=1",
+            "--process-id=host",
+            "--granularity=second",
+            "--page-num=first",
+        ])
+        .unwrap();
+    let errors = Measurements::from_arg_matches(&matches).err().unwrap();
+    let fields: Vec<_> = errors
+        .errors()
+        .iter()
+        .map(|error| match error {
+            TryFromMapError::ParseError { field, .. } => field.as_str(),
+            error => panic!("unexpected error: {error}"),
+        })
+        .collect();
+    assert_eq!(fields, ["granularity", "pageNum"]);
+}
+
+#[test]
+fn test_help() {
+    let command = command();
+    let arg = |id: &str| {
+        command
+            .get_arguments()
+            .find(|arg| arg.get_id() == id)
+            .unwrap()
+    };
+
+    assert_eq!(arg("groupId").get_long(), Some("group-id"));
+    assert!(arg("groupId").is_required_set());
+    assert!(!arg("includeCount").is_required_set());
+    assert!(!arg("pageNum").is_required_set());
+    assert_eq!(
+        arg("groupId").get_help().unwrap().to_string(),
+        "The project of the process."
+    );
+    assert!(arg("processId").get_help().is_none());
+    assert_eq!(
+        arg("granularity").get_help().unwrap().to_string(),
+        "The duration between two data points."
+    );
+    assert_eq!(
+        arg("granularity").get_long_help().unwrap().to_string(),
+        "The duration between two data points.\n\nFiner granularities return more data points."
+    );
+    assert_eq!(arg("m").get_long(), Some("metrics"));
+}
+
+#[derive(TryFromMap, MapArgs, Debug, PartialEq)]
+struct Filtered {
+    name: String,
+    #[map(prefix = "filter.")]
+    filter: Page,
+}
+
+#[test]
+fn test_prefixed_args() {
+    let command = Filtered::augment_command(Command::new("filtered"));
+    let matches = command
+        .try_get_matches_from(["filtered", "--name=a", "--filter-page-num=2"])
+        .unwrap();
+    assert_eq!(
+        Filtered::from_arg_matches(&matches).unwrap(),
+        Filtered {
+            name: "a".to_string(),
+            filter: Page {
+                page_num: 2,
+                items_per_page: None,
+            },
+        }
+    );
+}
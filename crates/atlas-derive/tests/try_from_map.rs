@@ -1,6 +1,18 @@
+#![allow(clippy::approx_constant, clippy::bool_assert_comparison, clippy::disallowed_names)]
+
+use std::borrow::Cow;
 use std::collections::HashMap;
-use atlas_derive::TryFromMap;
-use atlas_derive_core::TryFromMapError;
+use std::path::PathBuf;
+use std::time::Duration;
+use atlas_derive::{MapEnum, TryFromMap};
+use atlas_derive_core::{ToMap, TryFromMap, TryFromMapError, TryFromMapErrors};
+use proptest::prelude::*;
+use url::Url;
+
+/// The errors of `result`, to match with a slice pattern.
+fn errors<T>(result: &Result<T, TryFromMapErrors>) -> Result<&T, &[TryFromMapError]> {
+    result.as_ref().map_err(TryFromMapErrors::errors)
+}
 
 #[derive(TryFromMap)]
 struct Example {
@@ -28,7 +40,7 @@ fn test_missing_required_field() {
     // Missing "bar" field
 
     let result = Example::try_from(map);
-    assert!(matches!(result, Err(TryFromMapError::MissingField(field)) if field == "bar"));
+    assert!(matches!(errors(&result), Err([TryFromMapError::MissingField(field)]) if field == "bar"));
 }
 
 #[test]
@@ -38,7 +50,7 @@ fn test_empty_required_field() {
     map.insert("bar".to_string(), vec![]);
 
     let result = Example::try_from(map);
-    assert!(matches!(result, Err(TryFromMapError::NoValuesInField(field)) if field == "bar"));
+    assert!(matches!(errors(&result), Err([TryFromMapError::NoValuesInField(field)]) if field == "bar"));
 }
 
 #[test]
@@ -48,10 +60,43 @@ fn test_parse_error() {
     map.insert("bar".to_string(), vec!["not a number".to_string()]);
 
     let result = Example::try_from(map);
-    assert!(matches!(result, Err(TryFromMapError::ParseError { field, value }) 
+    assert!(matches!(errors(&result), Err([TryFromMapError::ParseError { field, value, .. }]) 
         if field == "bar" && value == "not a number"));
 }
 
+#[test]
+fn test_all_errors() {
+    let mut map = HashMap::new();
+    map.insert("bar".to_string(), vec!["not a number".to_string()]);
+    map.insert("baz".to_string(), vec!["maybe".to_string()]);
+
+    let errors = Example::try_from(map).err().unwrap();
+    assert!(matches!(errors.errors(), [
+        TryFromMapError::MissingField(foo),
+        TryFromMapError::ParseError { field: bar, .. },
+        TryFromMapError::ParseError { field: baz, .. },
+    ] if foo == "foo" && bar == "bar" && baz == "baz"));
+
+    // Parse errors keep the error of `FromStr` as their source
+    let sources: Vec<_> = errors
+        .errors()
+        .iter()
+        .map(|error| std::error::Error::source(error).map(ToString::to_string))
+        .collect();
+    assert_eq!(sources, [
+        None,
+        Some("invalid digit found in string".to_string()),
+        Some("invalid flag 'maybe', expected one of true, false, 1, 0, yes, no, on, off".to_string()),
+    ]);
+    assert_eq!(
+        errors.to_string(),
+        "Missing field: foo; \
+         Failed to parse field 'bar' with value 'not a number': invalid digit found in string; \
+         Failed to parse field 'baz' with value 'maybe': \
+         invalid flag 'maybe', expected one of true, false, 1, 0, yes, no, on, off"
+    );
+}
+
 #[test]
 fn test_optional_field_some() {
     let mut map = HashMap::new();
@@ -221,7 +266,674 @@ fn test_parse_errors() {
         map.insert(field.to_string(), vec![invalid_value.to_string()]);
 
         let result = AllTypes::try_from(map);
-        assert!(matches!(result, Err(TryFromMapError::ParseError { field: f, value: v })
+        assert!(matches!(errors(&result), Err([TryFromMapError::ParseError { field: f, value: v, .. }])
             if f == field && v == invalid_value));
     }
 }
+
+#[derive(TryFromMap)]
+struct VecStyles {
+    repeated: Vec<u32>,
+    #[map(vec = "comma")]
+    comma: Vec<String>,
+    #[map(vec = "pipe")]
+    pipe: Option<Vec<String>>,
+    #[map(vec = "brackets")]
+    brackets: Vec<i32>,
+}
+
+#[test]
+fn test_vec_styles() {
+    let mut map = HashMap::new();
+    map.insert("repeated".to_string(), vec!["3".to_string(), "1".to_string()]);
+    map.insert("comma".to_string(), vec!["c,a".to_string(), "b".to_string()]);
+    map.insert("pipe".to_string(), vec!["x|y".to_string()]);
+    map.insert("brackets[]".to_string(), vec!["2".to_string(), "1".to_string()]);
+
+    let styles = VecStyles::try_from(map).unwrap();
+    assert_eq!(styles.repeated, vec![3, 1]);
+    assert_eq!(styles.comma, vec!["c", "a", "b"]);
+    assert_eq!(styles.pipe, Some(vec!["x".to_string(), "y".to_string()]));
+    assert_eq!(styles.brackets, vec![2, 1]);
+}
+
+#[test]
+fn test_vec_styles_empty_and_errors() {
+    let mut map = HashMap::new();
+    map.insert("repeated".to_string(), vec![]);
+    map.insert("comma".to_string(), vec!["".to_string()]);
+    map.insert("brackets[]".to_string(), vec!["1".to_string()]);
+
+    let styles = VecStyles::try_from(map.clone()).unwrap();
+    assert!(styles.repeated.is_empty());
+    assert!(styles.comma.is_empty());
+    assert_eq!(styles.pipe, None);
+
+    map.remove("brackets[]");
+    map.insert("brackets".to_string(), vec!["1".to_string()]);
+    let result = VecStyles::try_from(map.clone());
+    assert!(matches!(errors(&result), Err([TryFromMapError::MissingField(field)]) if field == "brackets[]"));
+
+    map.insert("brackets[]".to_string(), vec!["1".to_string()]);
+    map.insert("repeated".to_string(), vec!["1,2".to_string()]);
+    let result = VecStyles::try_from(map);
+    assert!(matches!(errors(&result), Err([TryFromMapError::ParseError { field, value, .. }])
+        if field == "repeated" && value == "1,2"));
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+struct Page<Id> {
+    group_id: Id,
+    ids: Vec<Id>,
+    page_num: Option<u32>,
+}
+
+#[test]
+fn test_generic_fields() {
+    let mut map = HashMap::new();
+    map.insert("group_id".to_string(), vec!["42".to_string()]);
+    map.insert("ids".to_string(), vec!["1".to_string(), "2".to_string()]);
+
+    let page = Page::<u64>::try_from(map.clone()).unwrap();
+    assert_eq!(
+        page,
+        Page {
+            group_id: 42,
+            ids: vec![1, 2],
+            page_num: None,
+        }
+    );
+
+    let page = Page::<String>::try_from(map).unwrap();
+    assert_eq!(page.group_id, "42");
+}
+
+/// Named like the type parameters of `TryFromMap::read_fields`.
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(to_map)]
+struct Entry<K, V> {
+    key: K,
+    values: Vec<V>,
+}
+
+#[test]
+fn test_generic_names() {
+    let entry = Entry::<String, u32>::try_from_query("key=a&values=1&values=2").unwrap();
+    assert_eq!(
+        entry,
+        Entry {
+            key: "a".to_string(),
+            values: vec![1, 2],
+        }
+    );
+    assert_eq!(Entry::try_from_map(&entry.to_map()).unwrap(), entry);
+}
+
+#[test]
+fn test_query_strings_and_urls() {
+    let page = Page::<String>::try_from_query("?group_id=a+b%20c&ids=1%2C2&ids=3").unwrap();
+    assert_eq!(page.group_id, "a b c");
+    assert_eq!(page.ids, ["1,2", "3"]);
+    assert_eq!(page.page_num, None);
+
+    // Keys are decoded too
+    let styles = VecStyles::try_from_query("repeated=1&comma=c%2Ca,b&pipe=x%7Cy&brackets%5B%5D=2").unwrap();
+    assert_eq!(styles.comma, vec!["c", "a", "b"]);
+    assert_eq!(styles.pipe, Some(vec!["x".to_string(), "y".to_string()]));
+    assert_eq!(styles.brackets, vec![2]);
+
+    let url = Url::parse("https://cloud.mongodb.com/api/atlas/v2/groups?group_id=1&ids=2&page_num=3").unwrap();
+    let page = Page::<u64>::try_from_url(&url).unwrap();
+    assert_eq!(
+        page,
+        Page {
+            group_id: 1,
+            ids: vec![2],
+            page_num: Some(3),
+        }
+    );
+
+    let result = Page::<u64>::try_from_url(&Url::parse("https://cloud.mongodb.com/?ids=x").unwrap());
+    assert!(matches!(errors(&result), Err([
+        TryFromMapError::MissingField(group_id),
+        TryFromMapError::ParseError { field: ids, .. },
+    ]) if group_id == "group_id" && ids == "ids"));
+}
+
+#[test]
+fn test_pairs_and_borrowed_maps() {
+    let page = Page::<u64>::try_from_pairs([("ids", "1"), ("group_id", "2"), ("ids", "3")]).unwrap();
+    assert_eq!(page.group_id, 2);
+    assert_eq!(page.ids, [1, 3]);
+
+    let page = Page::<u64>::try_from_pairs(vec![("group_id".to_string(), "2".to_string())]);
+    assert!(matches!(errors(&page), Err([TryFromMapError::MissingField(field)]) if field == "ids"));
+
+    let map: HashMap<&str, Vec<&str>> = HashMap::from([("group_id", vec!["4"]), ("ids", vec![])]);
+    let page = Page::<u64>::try_from_map(&map).unwrap();
+    assert_eq!(page.group_id, 4);
+    assert!(page.ids.is_empty());
+}
+
+
+fn default_page_size() -> u32 {
+    100
+}
+
+fn parse_duration(value: &str) -> Result<u64, String> {
+    match value.strip_suffix('s') {
+        Some(seconds) => seconds.parse().map_err(|_| value.to_string()),
+        None => Err(value.to_string()),
+    }
+}
+
+fn parse_flag(value: &str) -> Result<bool, &'static str> {
+    match value {
+        "1" | "on" => Ok(true),
+        "0" | "off" => Ok(false),
+        _ => Err("expected 1, 0, on or off"),
+    }
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(rename_all = "camelCase")]
+struct Search {
+    group_id: String,
+    #[map(default)]
+    page_num: u32,
+    #[map(default = "default_page_size")]
+    items_per_page: u32,
+    #[map(rename = "q")]
+    query: Option<String>,
+    #[map(parse_with = "parse_duration")]
+    timeout: u64,
+    #[map(parse_with = "parse_flag", vec = "comma")]
+    flags: Vec<bool>,
+    #[map(default)]
+    tags: Vec<String>,
+}
+
+fn map(pairs: &[(&str, &str)]) -> HashMap<String, Vec<String>> {
+    let mut map: HashMap<String, Vec<String>> = HashMap::new();
+    for (key, value) in pairs {
+        map.entry(key.to_string()).or_default().push(value.to_string());
+    }
+    map
+}
+
+#[test]
+fn test_rename_default_and_parse_with() {
+    let search = Search::try_from(map(&[
+        ("groupId", "1"),
+        ("q", "name:Cluster0"),
+        ("timeout", "30s"),
+        ("flags", "1,off"),
+    ]))
+    .unwrap();
+    assert_eq!(
+        search,
+        Search {
+            group_id: "1".to_string(),
+            page_num: 0,
+            items_per_page: 100,
+            query: Some("name:Cluster0".to_string()),
+            timeout: 30,
+            flags: vec![true, false],
+            tags: vec![],
+        }
+    );
+
+    let search = Search::try_from(map(&[
+        ("groupId", "1"),
+        ("pageNum", "3"),
+        ("itemsPerPage", "10"),
+        ("timeout", "5s"),
+        ("flags", ""),
+        ("tags", "a"),
+        ("tags", "b"),
+    ]))
+    .unwrap();
+    assert_eq!(search.page_num, 3);
+    assert_eq!(search.items_per_page, 10);
+    assert_eq!(search.query, None);
+    assert!(search.flags.is_empty());
+    assert_eq!(search.tags, ["a", "b"]);
+}
+
+#[test]
+fn test_rename_default_and_parse_with_errors() {
+    // The key is the renamed one
+    let result = Search::try_from(map(&[("group_id", "1"), ("timeout", "5s"), ("flags", "")]));
+    assert!(matches!(errors(&result), Err([TryFromMapError::MissingField(field)]) if field == "groupId"));
+
+    let result = Search::try_from(map(&[("groupId", "1"), ("timeout", "5"), ("flags", "")]));
+    assert!(matches!(errors(&result), Err([TryFromMapError::ParseError { field, value, .. }])
+        if field == "timeout" && value == "5"));
+
+    let result = Search::try_from(map(&[("groupId", "1"), ("timeout", "5s"), ("flags", "1,yes")]));
+    assert!(matches!(errors(&result), Err([TryFromMapError::ParseError { field, value, .. }])
+        if field == "flags" && value == "yes"));
+    let source = std::error::Error::source(&result.unwrap_err().errors()[0]).unwrap().to_string();
+    assert_eq!(source, "expected 1, 0, on or off");
+
+    // Defaults don't hide values which fail to parse
+    let result = Search::try_from(map(&[
+        ("groupId", "1"),
+        ("pageNum", "first"),
+        ("timeout", "5s"),
+        ("flags", ""),
+    ]));
+    assert!(matches!(errors(&result), Err([TryFromMapError::ParseError { field, value, .. }])
+        if field == "pageNum" && value == "first"));
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(deny_unknown_fields, rename_all = "camelCase")]
+struct StrictSearch {
+    group_id: String,
+    #[map(single)]
+    page_num: Option<u32>,
+    #[map(vec = "brackets")]
+    tags: Vec<String>,
+    // Takes the first value
+    sort: Option<String>,
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(single)]
+struct SingleValues {
+    name: String,
+    #[map(default)]
+    limit: u32,
+    ids: Vec<u32>,
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(deny_unknown_fields)]
+struct NoFields {}
+
+#[test]
+fn test_deny_unknown_fields() {
+    let search = StrictSearch::try_from_query("groupId=1&tags[]=a&tags[]=b&sort=x&sort=y").unwrap();
+    assert_eq!(
+        search,
+        StrictSearch {
+            group_id: "1".to_string(),
+            page_num: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+            sort: Some("x".to_string()),
+        }
+    );
+
+    let result = StrictSearch::try_from_query("groupId=1&tags[]=a&tags=b&pagenum=2&group_id=1");
+    assert!(matches!(errors(&result), Err([
+        TryFromMapError::UnknownField(group_id),
+        TryFromMapError::UnknownField(pagenum),
+        TryFromMapError::UnknownField(tags),
+    ]) if group_id == "group_id" && pagenum == "pagenum" && tags == "tags"));
+
+    assert!(NoFields::try_from_query("").is_ok());
+    let result = NoFields::try_from_query("a=1");
+    assert!(matches!(errors(&result), Err([TryFromMapError::UnknownField(field)]) if field == "a"));
+}
+
+#[test]
+fn test_single() {
+    let result = StrictSearch::try_from_query("groupId=1&tags[]=a&pageNum=1&pageNum=2");
+    assert!(matches!(errors(&result), Err([TryFromMapError::TooManyValues(field)]) if field == "pageNum"));
+
+    let values = SingleValues::try_from_query("name=a&ids=1&ids=2").unwrap();
+    assert_eq!(
+        values,
+        SingleValues {
+            name: "a".to_string(),
+            limit: 0,
+            ids: vec![1, 2],
+        }
+    );
+
+    let result = SingleValues::try_from_query("name=a&name=b&limit=1&limit=1&ids=1");
+    assert!(matches!(errors(&result), Err([
+        TryFromMapError::TooManyValues(name),
+        TryFromMapError::TooManyValues(limit),
+    ]) if name == "name" && limit == "limit"));
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(rename_all = "camelCase", to_map)]
+struct Pagination {
+    #[map(default)]
+    page_num: u32,
+    items_per_page: Option<u32>,
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(to_map)]
+struct Filter {
+    name: Option<String>,
+    #[map(vec = "comma")]
+    tags: Vec<String>,
+    #[map(prefix = "created.")]
+    created: Range,
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(to_map)]
+struct Range {
+    after: Option<u64>,
+    before: Option<u64>,
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(deny_unknown_fields, to_map)]
+struct ListClusters {
+    group_id: String,
+    #[map(flatten)]
+    pagination: Pagination,
+    #[map(prefix = "filter.")]
+    filter: Filter,
+}
+
+#[test]
+fn test_flatten_and_prefix() {
+    let clusters = ListClusters::try_from_query(
+        "group_id=1&pageNum=2&filter.name=Cluster0&filter.tags=a,b&filter.created.after=10",
+    )
+    .unwrap();
+    assert_eq!(
+        clusters,
+        ListClusters {
+            group_id: "1".to_string(),
+            pagination: Pagination {
+                page_num: 2,
+                items_per_page: None,
+            },
+            filter: Filter {
+                name: Some("Cluster0".to_string()),
+                tags: vec!["a".to_string(), "b".to_string()],
+                created: Range {
+                    after: Some(10),
+                    before: None,
+                },
+            },
+        }
+    );
+
+    assert!(ListClusters::has_field("itemsPerPage"));
+    assert!(ListClusters::has_field("filter.created.before"));
+    assert!(!ListClusters::has_field("tags"));
+    assert!(!ListClusters::has_field("filter.pageNum"));
+}
+
+#[test]
+fn test_flatten_and_prefix_errors() {
+    // The keys of the errors include the prefixes, and the nested keys aren't unknown
+    let result = ListClusters::try_from_query(
+        "itemsPerPage=x&filter.created.before=y&filter.tags=a&filter.name=b&filter.other=1",
+    );
+    assert!(matches!(errors(&result), Err([
+        TryFromMapError::MissingField(group_id),
+        TryFromMapError::ParseError { field: items_per_page, .. },
+        TryFromMapError::ParseError { field: before, .. },
+        TryFromMapError::UnknownField(other),
+    ]) if group_id == "group_id"
+        && items_per_page == "itemsPerPage"
+        && before == "filter.created.before"
+        && other == "filter.other"));
+
+    let result = ListClusters::try_from_query("group_id=1");
+    assert!(matches!(errors(&result), Err([TryFromMapError::MissingField(field)]) if field == "filter.tags"));
+}
+
+#[derive(MapEnum, Debug, Clone, Copy, PartialEq)]
+#[map(rename_all = "SCREAMING_SNAKE_CASE")]
+enum ClusterType {
+    Replicaset,
+    Sharded,
+    GeoSharded,
+    #[map(rename = "SERVERLESS_INSTANCE")]
+    Serverless,
+}
+
+#[derive(MapEnum, Debug, Clone, Copy, PartialEq)]
+#[map(rename_all = "camelCase")]
+enum Granularity {
+    OneMinute,
+    OneHour,
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(rename_all = "camelCase", to_map)]
+struct ListFlags {
+    #[map(default)]
+    include_count: bool,
+    pretty: Option<bool>,
+    #[map(vec = "comma")]
+    envelope: Vec<bool>,
+    cluster_type: Option<ClusterType>,
+    #[map(vec = "comma", default)]
+    granularities: Vec<Granularity>,
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+struct Toggle {
+    enabled: bool,
+}
+
+#[test]
+fn test_flags() {
+    let flags = ListFlags::try_from_query("includeCount&pretty=YES&envelope=1,0,on,Off,true").unwrap();
+    assert_eq!(flags.include_count, true);
+    assert_eq!(flags.pretty, Some(true));
+    assert_eq!(flags.envelope, [true, false, true, false, true]);
+
+    let flags = ListFlags::try_from_query("includeCount=no&pretty=0&envelope=").unwrap();
+    assert_eq!(flags.include_count, false);
+    assert_eq!(flags.pretty, Some(false));
+    assert!(flags.envelope.is_empty());
+
+    let flags = ListFlags::try_from_query("envelope").unwrap();
+    assert_eq!(flags.include_count, false);
+    assert_eq!(flags.pretty, None);
+
+    // A key without values sets a flag, but not an optional one
+    let map: HashMap<&str, Vec<&str>> =
+        HashMap::from([("includeCount", vec![]), ("pretty", vec![]), ("envelope", vec![])]);
+    let flags = ListFlags::try_from_map(&map).unwrap();
+    assert_eq!(flags.include_count, true);
+    assert_eq!(flags.pretty, None);
+    assert!(flags.envelope.is_empty());
+    let map: HashMap<&str, Vec<&str>> = HashMap::from([("enabled", vec![])]);
+    assert_eq!(Toggle::try_from_map(&map).unwrap(), Toggle { enabled: true });
+
+    let result = ListFlags::try_from_query("pretty=maybe&envelope=");
+    assert!(matches!(errors(&result), Err([TryFromMapError::ParseError { field, value, .. }])
+        if field == "pretty" && value == "maybe"));
+}
+
+#[test]
+fn test_enums() {
+    let flags = ListFlags::try_from_query("envelope=&clusterType=geo_sharded&granularities=ONEMINUTE,oneHour").unwrap();
+    assert_eq!(flags.cluster_type, Some(ClusterType::GeoSharded));
+    assert_eq!(flags.granularities, [Granularity::OneMinute, Granularity::OneHour]);
+
+    assert_eq!(ClusterType::Replicaset.to_string(), "REPLICASET");
+    assert_eq!(ClusterType::Serverless.to_string(), "SERVERLESS_INSTANCE");
+    assert_eq!("serverless_instance".parse(), Ok(ClusterType::Serverless));
+    assert_eq!(Granularity::OneHour.to_string(), "oneHour");
+
+    let error = "SERVERLESS".parse::<ClusterType>().unwrap_err();
+    assert_eq!(error.value(), "SERVERLESS");
+    assert_eq!(
+        error.to_string(),
+        "unknown variant 'SERVERLESS', expected one of REPLICASET, SHARDED, GEO_SHARDED, SERVERLESS_INSTANCE"
+    );
+
+    let flags = ListFlags {
+        include_count: true,
+        pretty: None,
+        envelope: vec![],
+        cluster_type: Some(ClusterType::Sharded),
+        granularities: vec![Granularity::OneMinute],
+    };
+    assert_eq!(ListFlags::try_from_map(&flags.to_map()).unwrap(), flags);
+}
+
+#[test]
+fn test_borrowed_values() {
+    let map: HashMap<&str, Vec<Cow<str>>> = HashMap::from([
+        ("includeCount", vec![Cow::Borrowed("1")]),
+        ("envelope", vec![Cow::Owned("0".to_string())]),
+        ("clusterType", vec![Cow::Borrowed("sharded")]),
+    ]);
+    let flags = ListFlags::try_from_map(&map).unwrap();
+    assert_eq!(flags.include_count, true);
+    assert_eq!(flags.envelope, [false]);
+    assert_eq!(flags.cluster_type, Some(ClusterType::Sharded));
+
+    let map: HashMap<Cow<str>, Vec<&str>> = HashMap::from([(Cow::Borrowed("envelope"), vec!["yes"])]);
+    assert_eq!(ListFlags::try_from_map(&map).unwrap().envelope, [true]);
+}
+
+#[derive(TryFromMap, Debug, Clone, PartialEq)]
+#[map(rename_all = "camelCase", to_map)]
+struct Measurements {
+    group_id: String,
+    #[map(rename = "m")]
+    metrics: Vec<String>,
+    #[map(vec = "comma")]
+    granularity: Option<Vec<String>>,
+    #[map(vec = "pipe")]
+    hosts: Vec<String>,
+    #[map(vec = "brackets")]
+    tags: Vec<i64>,
+    period: Option<u32>,
+    include_count: bool,
+    ratio: f64,
+}
+
+#[test]
+fn test_to_map() {
+    let measurements = Measurements {
+        group_id: "1".to_string(),
+        metrics: vec!["a".to_string(), "b".to_string()],
+        granularity: None,
+        hosts: vec!["x".to_string(), "y".to_string()],
+        tags: vec![],
+        period: Some(60),
+        include_count: true,
+        ratio: 0.5,
+    };
+    let map = HashMap::from(&measurements);
+    assert_eq!(
+        map,
+        HashMap::from([
+            ("groupId".to_string(), vec!["1".to_string()]),
+            ("m".to_string(), vec!["a".to_string(), "b".to_string()]),
+            ("hosts".to_string(), vec!["x|y".to_string()]),
+            ("tags[]".to_string(), vec![]),
+            ("period".to_string(), vec!["60".to_string()]),
+            ("includeCount".to_string(), vec!["true".to_string()]),
+            ("ratio".to_string(), vec!["0.5".to_string()]),
+        ])
+    );
+
+    let clusters = ListClusters {
+        group_id: "1".to_string(),
+        pagination: Pagination {
+            page_num: 2,
+            items_per_page: None,
+        },
+        filter: Filter {
+            name: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+            created: Range {
+                after: Some(10),
+                before: None,
+            },
+        },
+    };
+    assert_eq!(
+        clusters.to_map(),
+        HashMap::from([
+            ("group_id".to_string(), vec!["1".to_string()]),
+            ("pageNum".to_string(), vec!["2".to_string()]),
+            ("filter.tags".to_string(), vec!["a,b".to_string()]),
+            ("filter.created.after".to_string(), vec!["10".to_string()]),
+        ])
+    );
+}
+
+fn parse_seconds(value: &str) -> Result<Duration, std::num::ParseIntError> {
+    value.parse().map(Duration::from_secs)
+}
+
+/// Without `#[map(to_map)]` the fields don't need to implement `Display`.
+#[derive(TryFromMap, Debug, PartialEq)]
+struct Export {
+    path: PathBuf,
+    #[map(parse_with = "parse_seconds")]
+    timeout: Duration,
+}
+
+#[test]
+fn test_without_to_map() {
+    assert_eq!(
+        Export::try_from_query("path=/tmp/export.json&timeout=30").unwrap(),
+        Export {
+            path: PathBuf::from("/tmp/export.json"),
+            timeout: Duration::from_secs(30),
+        }
+    );
+}
+
+/// Non-empty values which don't contain the separators of joined `Vec`s.
+fn element() -> impl Strategy<Value = String> {
+    "[^,|]{1,8}"
+}
+
+proptest! {
+    #[test]
+    fn round_trip_measurements(
+        group_id: String,
+        metrics: Vec<String>,
+        // `Some` of an empty `Vec` reads back as `None`
+        granularity in prop::option::of(prop::collection::vec(element(), 1..4)),
+        hosts in prop::collection::vec(element(), 0..4),
+        tags: Vec<i64>,
+        period: Option<u32>,
+        include_count: bool,
+        ratio in any::<f64>().prop_filter("NaN", |ratio| !ratio.is_nan()),
+    ) {
+        let measurements = Measurements {
+            group_id,
+            metrics,
+            granularity,
+            hosts,
+            tags,
+            period,
+            include_count,
+            ratio,
+        };
+        let map = HashMap::from(&measurements);
+        prop_assert_eq!(Measurements::try_from(map).unwrap(), measurements);
+    }
+
+    #[test]
+    fn round_trip_nested(
+        group_id: String,
+        page_num: u32,
+        items_per_page: Option<u32>,
+        name: Option<String>,
+        tags in prop::collection::vec(element(), 0..4),
+        after: Option<u64>,
+        before: Option<u64>,
+    ) {
+        let clusters = ListClusters {
+            group_id,
+            pagination: Pagination { page_num, items_per_page },
+            filter: Filter { name, tags, created: Range { after, before } },
+        };
+        prop_assert_eq!(ListClusters::try_from_map(&clusters.to_map()).unwrap(), clusters);
+    }
+}
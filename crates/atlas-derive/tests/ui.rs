@@ -0,0 +1,8 @@
+/// The expected errors are rustc's, whose wording and spans change between releases, so they
+/// are only checked on stable.
+#[rustversion::attr(not(stable), ignore)]
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,11 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters")]
+struct CreateCluster {
+    group_id: String,
+    #[url(body)]
+    cluster: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/peers/{group_id}")]
+struct Peers {
+    group_id: String,
+}
+
+fn main() {}
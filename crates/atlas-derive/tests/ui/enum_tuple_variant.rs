@@ -0,0 +1,9 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+enum ClusterEndpoint {
+    #[url("/api/atlas/v2/groups/{group_id}/clusters")]
+    List(String),
+}
+
+fn main() {}
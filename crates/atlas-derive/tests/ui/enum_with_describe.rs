@@ -0,0 +1,10 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url(describe)]
+enum ClusterEndpoint {
+    #[url("/api/atlas/v2/groups/{group_id}/clusters")]
+    List { group_id: String },
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use atlas_derive::MapEnum;
+
+#[derive(MapEnum)]
+enum ClusterType {
+    Replicaset,
+    Sharded { shards: u32 },
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use atlas_derive::TryFromMap;
+
+#[derive(TryFromMap)]
+struct Page {
+    page_num: u32,
+}
+
+#[derive(TryFromMap)]
+struct Search {
+    #[map(flatten, rename = "page")]
+    page: Page,
+}
+
+fn main() {}
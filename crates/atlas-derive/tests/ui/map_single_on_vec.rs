@@ -0,0 +1,9 @@
+use atlas_derive::TryFromMap;
+
+#[derive(TryFromMap)]
+struct Search {
+    #[map(single)]
+    tags: Vec<String>,
+}
+
+fn main() {}
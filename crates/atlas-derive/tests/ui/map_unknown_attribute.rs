@@ -0,0 +1,9 @@
+use atlas_derive::TryFromMap;
+
+#[derive(TryFromMap)]
+struct Search {
+    #[map(alias = "q")]
+    query: String,
+}
+
+fn main() {}
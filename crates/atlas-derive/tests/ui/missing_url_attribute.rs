@@ -0,0 +1,8 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+struct Clusters {
+    group_id: String,
+}
+
+fn main() {}
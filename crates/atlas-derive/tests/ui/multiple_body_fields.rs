@@ -0,0 +1,13 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters", method = "POST")]
+struct CreateCluster {
+    group_id: String,
+    #[url(body)]
+    cluster: String,
+    #[url(body)]
+    settings: String,
+}
+
+fn main() {}
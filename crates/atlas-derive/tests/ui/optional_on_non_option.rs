@@ -0,0 +1,11 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters/{name}")]
+struct Cluster {
+    group_id: String,
+    #[url(optional)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters")]
+struct Clusters {
+    group_id: String,
+    #[url(path)]
+    cluster_name: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use atlas_derive::TryFromMap;
+
+#[derive(TryFromMap)]
+enum ClusterType {
+    Replicaset,
+    Sharded,
+}
+
+fn main() {}
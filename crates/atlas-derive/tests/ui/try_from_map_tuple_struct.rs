@@ -0,0 +1,6 @@
+use atlas_derive::TryFromMap;
+
+#[derive(TryFromMap)]
+struct ClusterName(String);
+
+fn main() {}
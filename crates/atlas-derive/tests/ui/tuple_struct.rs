@@ -0,0 +1,7 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters")]
+struct Clusters(String);
+
+fn main() {}
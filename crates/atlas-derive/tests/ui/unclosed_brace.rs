@@ -0,0 +1,9 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id/clusters")]
+struct Clusters {
+    group_id: String,
+}
+
+fn main() {}
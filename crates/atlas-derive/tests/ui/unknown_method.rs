@@ -0,0 +1,9 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}", method = "FETCH")]
+struct Project {
+    group_id: String,
+}
+
+fn main() {}
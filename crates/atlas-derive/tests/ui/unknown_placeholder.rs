@@ -0,0 +1,10 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters/{clustr_name}")]
+struct Cluster {
+    group_id: String,
+    cluster_name: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups", rename_all = "camel")]
+struct Groups {
+    items_per_page: u32,
+}
+
+fn main() {}
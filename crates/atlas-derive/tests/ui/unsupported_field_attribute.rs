@@ -0,0 +1,10 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters")]
+struct Clusters {
+    #[url(encoded)]
+    group_id: String,
+}
+
+fn main() {}
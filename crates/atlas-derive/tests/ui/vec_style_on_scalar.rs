@@ -0,0 +1,10 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups")]
+struct Groups {
+    #[url(vec = "comma")]
+    name: String,
+}
+
+fn main() {}
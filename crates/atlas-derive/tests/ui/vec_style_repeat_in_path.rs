@@ -0,0 +1,10 @@
+use atlas_core::AtlasURL;
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/users/{user_ids}")]
+struct Users {
+    #[url(vec = "repeat")]
+    user_ids: Vec<String>,
+}
+
+fn main() {}
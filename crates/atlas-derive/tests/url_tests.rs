@@ -1,4 +1,6 @@
-use atlas_derive_core::AsUrl;
+#![allow(clippy::approx_constant)]
+
+use atlas_derive_core::{AsUrl, AsUrlError, AtlasRequest, FromUrl, Method};
 use atlas_core::AtlasURL;
 
 #[test]
@@ -414,3 +416,588 @@ fn test_optional_vec_types() {
 
     assert_eq!(url.query(), None);
 }
+
+#[test]
+fn test_path_params_reserved_characters() {
+    #[derive(AtlasURL)]
+    #[url("/api/groups/{group_id}/databaseUsers/{username}")]
+    struct TestUrl {
+        group_id: String,
+        username: String,
+    }
+
+    let url = TestUrl {
+        group_id: "a/b".to_string(),
+        username: "user?name#1 %".to_string(),
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+
+    assert_eq!(
+        url.path(),
+        "/api/groups/a%2Fb/databaseUsers/user%3Fname%231%20%25"
+    );
+    assert_eq!(url.query(), None);
+    assert_eq!(url.fragment(), None);
+
+    // Sub-delimiters, `:` and `@` are allowed in a path segment
+    let url = TestUrl {
+        group_id: "a-b.c_d~e".to_string(),
+        username: "CN=user,O=org:x@y".to_string(),
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+
+    assert_eq!(url.path(), "/api/groups/a-b.c_d~e/databaseUsers/CN=user,O=org:x@y");
+}
+
+#[test]
+fn test_path_params_unicode() {
+    #[derive(AtlasURL)]
+    #[url("/api/clusters/{name}")]
+    struct TestUrl {
+        name: String,
+    }
+
+    let url = TestUrl {
+        name: "clüster 日本".to_string(),
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+
+    assert_eq!(url.path(), "/api/clusters/cl%C3%BCster%20%E6%97%A5%E6%9C%AC");
+}
+
+#[test]
+fn test_path_params_vec_encoded() {
+    #[derive(AtlasURL)]
+    #[url("/api/{names}/{label}")]
+    struct TestUrl {
+        names: Vec<String>,
+        label: String,
+    }
+
+    let url = TestUrl {
+        names: vec!["a/b".to_string(), "c d".to_string()],
+        label: "x?y".to_string(),
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+
+    assert_eq!(url.path(), "/api/a%2Fb,c%20d/x%3Fy");
+}
+
+#[test]
+fn test_path_params_raw() {
+    #[derive(AtlasURL)]
+    #[url("/api/groups/{group_id}/accessList/{entry}")]
+    struct TestUrl {
+        group_id: String,
+        #[url(raw)]
+        entry: String,
+    }
+
+    let url = TestUrl {
+        group_id: "1 2".to_string(),
+        entry: "10.0.0.0%2F16".to_string(),
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+
+    assert_eq!(url.path(), "/api/groups/1%202/accessList/10.0.0.0%2F16");
+}
+
+#[test]
+fn test_rename_and_skip() {
+    #[derive(AtlasURL)]
+    #[url("/api/atlas/v2/groups/{group_id}/clusters")]
+    struct TestUrl {
+        group_id: String,
+        #[url(rename = "itemsPerPage")]
+        items_per_page: Option<u32>,
+        #[url(rename = "pageNum")]
+        page_num: u32,
+        #[url(skip)]
+        #[allow(dead_code)]
+        cache_key: String,
+    }
+
+    let url = TestUrl {
+        group_id: "1".to_string(),
+        items_per_page: Some(10),
+        page_num: 2,
+        cache_key: "ignored".to_string(),
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+
+    assert_eq!(url.path(), "/api/atlas/v2/groups/1/clusters");
+    assert_eq!(url.query(), Some("itemsPerPage=10&pageNum=2"));
+}
+
+#[test]
+fn test_rename_all() {
+    #[derive(AtlasURL)]
+    #[url("/api/atlas/v2/groups/{groupId}/clusters/{cluster_name}", rename_all = "camelCase")]
+    struct TestUrl {
+        group_id: String,
+        cluster_name: String,
+        include_count: Option<bool>,
+        #[url(rename = "envelope")]
+        wrap_response: Option<bool>,
+        r#type: Option<String>,
+    }
+
+    let url = TestUrl {
+        group_id: "1".to_string(),
+        cluster_name: "Cluster0".to_string(),
+        include_count: Some(true),
+        wrap_response: Some(false),
+        r#type: Some("REPLICASET".to_string()),
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+
+    assert_eq!(url.path(), "/api/atlas/v2/groups/1/clusters/Cluster0");
+    assert_eq!(
+        url.query(),
+        Some("includeCount=true&envelope=false&type=REPLICASET")
+    );
+}
+
+#[test]
+fn test_rename_all_separate_attribute() {
+    #[derive(AtlasURL)]
+    #[url("/api/test")]
+    #[url(rename_all = "SCREAMING-KEBAB-CASE")]
+    struct TestUrl {
+        page_num: u32,
+    }
+
+    let url = TestUrl { page_num: 1 }
+        .as_url("http://jeroenvervaeke.com")
+        .unwrap();
+
+    assert_eq!(url.query(), Some("PAGE-NUM=1"));
+}
+
+#[test]
+fn test_explicit_placement() {
+    #[derive(AtlasURL)]
+    #[url("/api/orgs/{orgId}")]
+    struct TestUrl {
+        #[url(path, rename = "orgId")]
+        org_id: String,
+        #[url(query)]
+        name: String,
+    }
+
+    let url = TestUrl {
+        org_id: "1".to_string(),
+        name: "org".to_string(),
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+
+    assert_eq!(url.path(), "/api/orgs/1");
+    assert_eq!(url.query(), Some("name=org"));
+}
+
+#[test]
+fn test_vec_query_styles() {
+    #[derive(AtlasURL)]
+    #[url("/api/test")]
+    struct TestUrl {
+        repeated: Vec<u32>,
+        #[url(vec = "comma")]
+        comma: Vec<String>,
+        #[url(vec = "pipe")]
+        pipe: Option<Vec<String>>,
+        #[url(vec = "brackets")]
+        brackets: Vec<i32>,
+    }
+
+    let url = TestUrl {
+        repeated: vec![3, 1, 2],
+        comma: vec!["c".to_string(), "a".to_string(), "b".to_string()],
+        pipe: Some(vec!["x".to_string(), "y".to_string()]),
+        brackets: vec![2, 1],
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+
+    let query_pairs: Vec<_> = url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+    assert_eq!(
+        query_pairs,
+        [
+            ("repeated", "3"),
+            ("repeated", "1"),
+            ("repeated", "2"),
+            ("comma", "c,a,b"),
+            ("pipe", "x|y"),
+            ("brackets[]", "2"),
+            ("brackets[]", "1"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+    );
+
+    let url = TestUrl {
+        repeated: vec![],
+        comma: vec![],
+        pipe: Some(vec![]),
+        brackets: vec![],
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+    assert_eq!(url.query(), None);
+}
+
+#[test]
+fn test_vec_path_pipe() {
+    #[derive(AtlasURL)]
+    #[url("/api/users/{user_ids}")]
+    struct TestUrl {
+        #[url(vec = "pipe")]
+        user_ids: Vec<u32>,
+    }
+
+    let url = TestUrl {
+        user_ids: vec![2, 1, 3],
+    }
+    .as_url("http://jeroenvervaeke.com")
+    .unwrap();
+
+    assert_eq!(url.path(), "/api/users/2|1|3");
+}
+
+#[derive(AtlasURL)]
+#[url("/api/public/v1.0/groups/{group_id}/hosts")]
+struct Hosts {
+    group_id: String,
+    page_num: Option<u32>,
+}
+
+fn hosts() -> Hosts {
+    Hosts {
+        group_id: "1".to_string(),
+        page_num: Some(2),
+    }
+}
+
+#[test]
+fn test_base_url_slashes() {
+    for base_url in [
+        "https://cloud.mongodb.com",
+        "https://cloud.mongodb.com/",
+        "https://cloud.mongodb.com//",
+    ] {
+        let url = hosts().as_url(base_url).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://cloud.mongodb.com/api/public/v1.0/groups/1/hosts?page_num=2"
+        );
+    }
+}
+
+#[test]
+fn test_base_url_path_prefix() {
+    for base_url in [
+        "https://ops.example.com/opsmanager",
+        "https://ops.example.com/opsmanager/",
+    ] {
+        let url = hosts().as_url(base_url).unwrap();
+        assert_eq!(
+            url.path(),
+            "/opsmanager/api/public/v1.0/groups/1/hosts"
+        );
+    }
+
+    // The query and fragment of the base URL are dropped
+    let url = hosts().as_url("http://localhost:8080/prefix/?debug=true#top").unwrap();
+    assert_eq!(
+        url.as_str(),
+        "http://localhost:8080/prefix/api/public/v1.0/groups/1/hosts?page_num=2"
+    );
+}
+
+#[test]
+fn test_base_url_types() {
+    let base_url = url::Url::parse("https://ops.example.com/opsmanager/").unwrap();
+    let expected = "https://ops.example.com/opsmanager/api/public/v1.0/groups/1/hosts?page_num=2";
+
+    assert_eq!(hosts().as_url(&base_url).unwrap().as_str(), expected);
+    assert_eq!(hosts().as_url(base_url.clone()).unwrap().as_str(), expected);
+    let base_url = base_url.to_string();
+    assert_eq!(hosts().as_url(&base_url).unwrap().as_str(), expected);
+    assert_eq!(hosts().as_url(base_url).unwrap().as_str(), expected);
+}
+
+#[test]
+fn test_base_url_errors() {
+    let result = hosts().as_url("not a url");
+    assert!(matches!(result, Err(AsUrlError::InvalidBaseUrl { base_url, source: url::ParseError::RelativeUrlWithoutBase })
+        if base_url == "not a url"));
+
+    let result = hosts().as_url("mailto:user@example.com");
+    assert!(matches!(result, Err(AsUrlError::CannotBeABase(base_url)) if base_url == "mailto:user@example.com"));
+}
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters/{cluster_name}")]
+#[url(method = "PATCH", version = "2023-02-01", response = Vec<String>)]
+struct UpdateCluster {
+    group_id: String,
+    cluster_name: String,
+    pretty: bool,
+    #[url(body)]
+    cluster: String,
+}
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}", method = "DELETE")]
+struct DeleteProject {
+    group_id: String,
+    #[url(body)]
+    reason: Option<String>,
+}
+
+#[test]
+fn test_atlas_request() {
+    let request = UpdateCluster {
+        group_id: "1".to_string(),
+        cluster_name: "Cluster0".to_string(),
+        pretty: true,
+        cluster: "{\"paused\":true}".to_string(),
+    };
+    assert_eq!(UpdateCluster::METHOD, Method::Patch);
+    assert_eq!(UpdateCluster::VERSION, Some("2023-02-01"));
+    assert_eq!(
+        UpdateCluster::media_type(),
+        "application/vnd.atlas.2023-02-01+json"
+    );
+    assert_eq!(request.body(), Some(&request.cluster));
+
+    // The body isn't part of the URL
+    let url = request.as_url("https://cloud.mongodb.com").unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/clusters/Cluster0?pretty=true"
+    );
+
+    let request = DeleteProject {
+        group_id: "1".to_string(),
+        reason: None,
+    };
+    assert_eq!(DeleteProject::METHOD, Method::Delete);
+    assert_eq!(DeleteProject::VERSION, None);
+    assert_eq!(DeleteProject::media_type(), "application/json");
+    assert_eq!(request.body(), None);
+    let request = DeleteProject {
+        reason: Some("unused".to_string()),
+        ..request
+    };
+    assert_eq!(request.body().map(String::as_str), Some("unused"));
+}
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters/{name}", method = "GET")]
+struct GetCluster<'a> {
+    group_id: &'a str,
+    name: &'a str,
+    #[url(vec = "comma")]
+    fields: Vec<&'a str>,
+    include_count: Option<bool>,
+}
+
+#[derive(AtlasURL, Debug, PartialEq)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters", method = "POST", response = B)]
+struct CreateCluster<Id, B>
+where
+    B: Clone,
+{
+    group_id: Id,
+    page_num: Option<Id>,
+    #[url(body)]
+    body: B,
+}
+
+#[test]
+fn test_borrowed_fields() {
+    let name = String::from("Cluster 0");
+    let request = GetCluster {
+        group_id: "1",
+        name: &name,
+        fields: vec!["name", "stateName"],
+        include_count: None,
+    };
+    let url = request.as_url("https://cloud.mongodb.com").unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/clusters/Cluster%200?fields=name%2CstateName"
+    );
+    assert_eq!(GetCluster::METHOD, Method::Get);
+}
+
+#[test]
+fn test_generic_fields() {
+    let request = CreateCluster {
+        group_id: 42u64,
+        page_num: Some(2),
+        body: "cluster".to_string(),
+    };
+    let url = request.as_url("https://cloud.mongodb.com").unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/42/clusters?page_num=2"
+    );
+    assert_eq!(request.body(), Some(&"cluster".to_string()));
+
+    let parsed = CreateCluster::<u64, String>::from_url(&url).unwrap();
+    assert_eq!(
+        parsed,
+        CreateCluster {
+            body: String::new(),
+            ..request
+        }
+    );
+}
+
+#[derive(AtlasURL, Debug, PartialEq)]
+#[url(rename_all = "camelCase")]
+enum ClusterEndpoint {
+    #[url("/api/atlas/v2/groups/{group_id}/clusters")]
+    List {
+        group_id: String,
+        items_per_page: Option<u32>,
+    },
+    #[url("/api/atlas/v2/groups/{group_id}/clusters/{name}")]
+    Get { group_id: String, name: String },
+    #[url("/api/atlas/v2/groups/{group_id}/clusters/{name}/processArgs", rename_all = "snake_case")]
+    ProcessArgs {
+        group_id: String,
+        name: String,
+        include_defaults: bool,
+    },
+    #[url("/api/atlas/v2/clusters")]
+    All,
+}
+
+#[test]
+fn test_enum_variants() {
+    let base_url = "https://cloud.mongodb.com";
+    let list = ClusterEndpoint::List {
+        group_id: "1".to_string(),
+        items_per_page: Some(10),
+    };
+    assert_eq!(
+        list.as_url(base_url).unwrap().as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/clusters?itemsPerPage=10"
+    );
+
+    let get = ClusterEndpoint::Get {
+        group_id: "1".to_string(),
+        name: "Cluster 0".to_string(),
+    };
+    assert_eq!(
+        get.as_url(base_url).unwrap().as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/clusters/Cluster%200"
+    );
+
+    let process_args = ClusterEndpoint::ProcessArgs {
+        group_id: "1".to_string(),
+        name: "Cluster0".to_string(),
+        include_defaults: true,
+    };
+    assert_eq!(
+        process_args.as_url(base_url).unwrap().as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/clusters/Cluster0/processArgs?include_defaults=true"
+    );
+
+    assert_eq!(
+        ClusterEndpoint::All.as_url(base_url).unwrap().as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/clusters"
+    );
+
+    // Parsing picks the variant whose pattern matches
+    for endpoint in [list, get, process_args, ClusterEndpoint::All] {
+        let url = endpoint.as_url(base_url).unwrap();
+        assert_eq!(ClusterEndpoint::from_url(&url).unwrap(), endpoint);
+    }
+}
+
+#[test]
+fn test_enum_mismatch() {
+    let url = url::Url::parse("https://cloud.mongodb.com/api/atlas/v2/groups/1").unwrap();
+    let result = ClusterEndpoint::from_url(&url);
+    assert!(matches!(result, Err(atlas_derive_core::FromUrlError::PathMismatch { pattern, .. })
+        if pattern == "/api/atlas/v2/groups/{group_id}/clusters | /api/atlas/v2/groups/{group_id}/clusters/{name} \
+            | /api/atlas/v2/groups/{group_id}/clusters/{name}/processArgs | /api/atlas/v2/clusters"));
+}
+
+#[derive(AtlasURL, Debug, PartialEq)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters/{name}")]
+struct ClusterOrClusters {
+    group_id: String,
+    #[url(optional)]
+    name: Option<String>,
+    page_num: Option<u32>,
+}
+
+#[derive(AtlasURL, Debug, PartialEq)]
+#[url("/api/atlas/v2/groups/{group_id}/processes/{hosts}/measurements")]
+struct OptionalHosts {
+    group_id: String,
+    #[url(optional, vec = "pipe")]
+    hosts: Option<Vec<String>>,
+}
+
+#[test]
+fn test_optional_path_params() {
+    let base_url = "https://cloud.mongodb.com";
+    let get = ClusterOrClusters {
+        group_id: "1".to_string(),
+        name: Some("Cluster 0".to_string()),
+        page_num: None,
+    };
+    let url = get.as_url(base_url).unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/clusters/Cluster%200"
+    );
+    assert_eq!(ClusterOrClusters::from_url(&url).unwrap(), get);
+
+    let list = ClusterOrClusters {
+        group_id: "1".to_string(),
+        name: None,
+        page_num: Some(2),
+    };
+    let url = list.as_url(base_url).unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/clusters?page_num=2"
+    );
+    assert_eq!(ClusterOrClusters::from_url(&url).unwrap(), list);
+
+    // The segment is omitted in the middle of the path too
+    let hosts = OptionalHosts {
+        group_id: "1".to_string(),
+        hosts: Some(vec!["a".to_string(), "b|c".to_string()]),
+    };
+    let url = hosts.as_url(base_url).unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/processes/a|b%7Cc/measurements"
+    );
+    assert_eq!(OptionalHosts::from_url(&url).unwrap(), hosts);
+
+    let no_hosts = OptionalHosts {
+        group_id: "1".to_string(),
+        hosts: None,
+    };
+    let url = no_hosts.as_url(base_url).unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://cloud.mongodb.com/api/atlas/v2/groups/1/processes/measurements"
+    );
+    assert_eq!(OptionalHosts::from_url(&url).unwrap(), no_hosts);
+}
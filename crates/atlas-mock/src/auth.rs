@@ -0,0 +1,113 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use atlas_core::client::{md5_hex, split_params};
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rand::Rng;
+
+use crate::{error::ApiError, AppState};
+
+pub(crate) const REALM: &str = "MMS Public API";
+
+/// Credentials accepted by the mock server.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Credentials {
+    /// Set once any credential is configured, until then every request is accepted.
+    pub(crate) required: bool,
+    /// Public key to private key.
+    pub(crate) api_keys: BTreeMap<String, String>,
+    pub(crate) access_tokens: BTreeSet<String>,
+    /// Nonces handed out in digest challenges.
+    pub(crate) nonces: BTreeSet<String>,
+}
+
+impl Credentials {
+    fn challenge(&mut self) -> String {
+        let nonce = format!("{:032x}", rand::thread_rng().gen::<u128>());
+        self.nonces.insert(nonce.clone());
+        format!(
+            r#"Digest realm="{REALM}", domain="", nonce="{nonce}", algorithm=MD5, qop="auth", stale=false"#
+        )
+    }
+
+    fn accepts(&self, authorization: &str, method: &str, uri: &str) -> bool {
+        if let Some(token) = authorization.strip_prefix("Bearer ") {
+            return self.access_tokens.contains(token.trim());
+        }
+
+        let Some(params) = authorization.strip_prefix("Digest ") else {
+            return false;
+        };
+        let params: BTreeMap<_, _> = split_params(params).into_iter().collect();
+        let param = |key: &str| params.get(key).map(String::as_str).unwrap_or_default();
+
+        let Some(private) = self.api_keys.get(param("username")) else {
+            return false;
+        };
+        if !self.nonces.contains(param("nonce")) {
+            return false;
+        }
+        // The digest only covers `uri`, which has to be the request's
+        if param("uri") != uri {
+            return false;
+        }
+
+        let ha1 = md5_hex(&format!(
+            "{}:{}:{private}",
+            param("username"),
+            param("realm")
+        ));
+        let ha2 = md5_hex(&format!("{method}:{}", param("uri")));
+        let expected = match params.get("qop") {
+            Some(qop) => md5_hex(&format!(
+                "{ha1}:{}:{}:{}:{qop}:{ha2}",
+                param("nonce"),
+                param("nc"),
+                param("cnonce")
+            )),
+            None => md5_hex(&format!("{ha1}:{}:{ha2}", param("nonce"))),
+        };
+
+        param("response") == expected
+    }
+}
+
+/// Rejects requests without valid bearer or digest credentials, answering with a digest
+/// challenge like Atlas does.
+pub(crate) async fn authenticate(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let authorized = {
+        let credentials = state.credentials();
+        !credentials.required
+            || request
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| {
+                    let uri = request
+                        .uri()
+                        .path_and_query()
+                        .map_or("/", |uri| uri.as_str());
+                    credentials.accepts(value, request.method().as_str(), uri)
+                })
+    };
+
+    if authorized {
+        return next.run(request).await;
+    }
+
+    let challenge = state.credentials().challenge();
+    let mut response = ApiError::unauthorized().into_response();
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        HeaderValue::from_str(&challenge).expect("valid header value"),
+    );
+    response
+}
@@ -0,0 +1,66 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+/// An error in the format returned by the Atlas Admin API.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiError {
+    pub detail: String,
+    pub error: u16,
+    pub error_code: String,
+    pub parameters: Vec<String>,
+    pub reason: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, error_code: &str, detail: impl Into<String>) -> Self {
+        Self {
+            detail: detail.into(),
+            error: status.as_u16(),
+            error_code: error_code.to_string(),
+            parameters: Vec::new(),
+            reason: status.canonical_reason().unwrap_or_default().to_string(),
+        }
+    }
+
+    pub fn with_parameters(mut self, parameters: impl IntoIterator<Item = String>) -> Self {
+        self.parameters = parameters.into_iter().collect();
+        self
+    }
+
+    pub(crate) fn unauthorized() -> Self {
+        Self::new(
+            StatusCode::UNAUTHORIZED,
+            "NOT_ATLAS_USER",
+            "You are not authorized for this resource.",
+        )
+    }
+
+    pub(crate) fn not_found(error_code: &str, detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, error_code, detail)
+    }
+
+    pub(crate) fn conflict(error_code: &str, detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, error_code, detail)
+    }
+
+    pub(crate) fn invalid_attribute(attribute: &str) -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "INVALID_ATTRIBUTE",
+            format!("Invalid attribute {attribute} specified."),
+        )
+        .with_parameters([attribute.to_string()])
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.error).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, Json(self)).into_response()
+    }
+}
@@ -0,0 +1,222 @@
+//! In-process mock of a subset of the MongoDB Atlas Admin API.
+//!
+//! Supports organizations, projects, clusters, database users and the IP access list,
+//! with digest (API keys) and bearer (OAuth) authentication, Atlas-style pagination and
+//! Atlas-style error bodies. It also stands in for the OAuth device authorization server,
+//! so tokens obtained through the device flow are accepted by the API routes.
+//!
+//! ```no_run
+//! # async fn example() -> std::io::Result<()> {
+//! let server = atlas_mock::MockServer::builder()
+//!     .api_key("public", "private")
+//!     .start()
+//!     .await?;
+//!
+//! let org_id = server.state().add_org("my-org");
+//! let group_id = server.state().add_project(&org_id, "my-project");
+//! println!("listening on {}", server.base_url());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use tokio::{net::TcpListener, task::JoinHandle};
+use url::Url;
+
+mod auth;
+mod error;
+mod extract;
+mod oauth;
+mod pagination;
+mod routes;
+mod state;
+
+pub use error::ApiError;
+pub use state::MockState;
+
+#[derive(Clone)]
+pub(crate) struct AppState {
+    base_url: Url,
+    mock: Arc<Mutex<MockState>>,
+    credentials: Arc<Mutex<auth::Credentials>>,
+    oauth: Arc<Mutex<oauth::OAuthState>>,
+}
+
+impl AppState {
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.mock.lock().expect("mock state poisoned")
+    }
+
+    fn credentials(&self) -> MutexGuard<'_, auth::Credentials> {
+        self.credentials.lock().expect("credentials poisoned")
+    }
+
+    fn oauth(&self) -> MutexGuard<'_, oauth::OAuthState> {
+        self.oauth.lock().expect("oauth state poisoned")
+    }
+}
+
+/// A running mock server, stopped when dropped.
+pub struct MockServer {
+    state: AppState,
+    task: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct MockServerBuilder {
+    state: MockState,
+    credentials: auth::Credentials,
+    oauth: oauth::OAuthState,
+}
+
+impl MockServerBuilder {
+    /// Accepts digest authentication with this API key pair.
+    pub fn api_key(mut self, public: impl Into<String>, private: impl Into<String>) -> Self {
+        self.credentials.required = true;
+        self.credentials
+            .api_keys
+            .insert(public.into(), private.into());
+        self
+    }
+
+    /// Accepts bearer authentication with this access token.
+    pub fn access_token(mut self, token: impl Into<String>) -> Self {
+        self.credentials.required = true;
+        self.credentials.access_tokens.insert(token.into());
+        self
+    }
+
+    /// Rejects unauthenticated requests even if no credentials were configured up front,
+    /// e.g. when all tokens are obtained through the device flow.
+    pub fn require_auth(mut self) -> Self {
+        self.credentials.required = true;
+        self
+    }
+
+    /// Polling interval advertised by the device authorization endpoint.
+    pub fn device_interval(mut self, interval: Duration) -> Self {
+        self.oauth.interval = interval;
+        self
+    }
+
+    /// Lifetime of the access tokens issued by the token endpoint.
+    pub fn access_token_lifetime(mut self, lifetime: Duration) -> Self {
+        self.oauth.access_token_lifetime = lifetime;
+        self
+    }
+
+    /// Whether refresh grants issue a new refresh token and invalidate the used one, which is
+    /// the default. Without rotation the response carries no refresh token and the old one
+    /// stays valid.
+    pub fn rotate_refresh_tokens(mut self, rotate: bool) -> Self {
+        self.oauth.rotate_refresh_tokens = rotate;
+        self
+    }
+
+    /// Starts with the given state instead of an empty deployment.
+    pub fn state(mut self, state: MockState) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Binds to a random port on localhost and starts serving.
+    pub async fn start(self) -> std::io::Result<MockServer> {
+        let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0))).await?;
+        let address = listener.local_addr()?;
+        let base_url = Url::parse(&format!("http://{address}/")).expect("valid base URL");
+
+        let state = AppState {
+            base_url,
+            mock: Arc::new(Mutex::new(self.state)),
+            credentials: Arc::new(Mutex::new(self.credentials)),
+            oauth: Arc::new(Mutex::new(self.oauth)),
+        };
+
+        let app = routes::router()
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth::authenticate,
+            ))
+            .merge(oauth::router())
+            .with_state(state.clone());
+
+        let task = tokio::spawn(async move {
+            axum::serve(listener, app)
+                .await
+                .expect("mock server failed");
+        });
+
+        Ok(MockServer { state, task })
+    }
+}
+
+impl MockServer {
+    pub fn builder() -> MockServerBuilder {
+        MockServerBuilder::default()
+    }
+
+    /// Starts a server without authentication and with an empty deployment.
+    pub async fn start() -> std::io::Result<Self> {
+        Self::builder().start().await
+    }
+
+    /// The URL the server listens on, e.g. `http://127.0.0.1:49152/`.
+    pub fn base_url(&self) -> &Url {
+        &self.state.base_url
+    }
+
+    /// Locks the in-memory deployment to inspect or seed it.
+    pub fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock()
+    }
+
+    /// Accepts an additional bearer token from now on, enabling authentication if it
+    /// wasn't required yet.
+    pub fn add_access_token(&self, token: impl Into<String>) {
+        let mut credentials = self.state.credentials();
+        credentials.required = true;
+        credentials.access_tokens.insert(token.into());
+    }
+
+    /// Stops accepting a bearer token.
+    pub fn remove_access_token(&self, token: &str) {
+        self.state.credentials().access_tokens.remove(token);
+    }
+
+    /// Approves the pending device authorization with this user code, as the user would
+    /// in the browser. Returns `false` if the code is unknown.
+    pub fn approve_device(&self, user_code: &str) -> bool {
+        self.state.oauth().approve(user_code)
+    }
+
+    /// Denies the pending device authorization with this user code.
+    pub fn deny_device(&self, user_code: &str) -> bool {
+        self.state.oauth().deny(user_code)
+    }
+
+    /// Number of successful `refresh_token` grants so far.
+    pub fn refresh_count(&self) -> usize {
+        self.state.oauth().refresh_count
+    }
+
+    /// Tokens passed to the revocation endpoint so far, in order.
+    pub fn revoked_tokens(&self) -> Vec<String> {
+        self.state.oauth().revoked_tokens.clone()
+    }
+
+    /// Makes the next token request fail with this OAuth error code, e.g. `slow_down`.
+    pub fn queue_token_error(&self, error: &str) {
+        self.state.oauth().queue_error(error);
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Form, Json, Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::AppState;
+
+pub(crate) const AUTHORIZE_PATH: &str = "/api/private/unauth/account/device/authorize";
+pub(crate) const TOKEN_PATH: &str = "/api/private/unauth/account/device/token";
+pub(crate) const REVOKE_PATH: &str = "/api/private/unauth/account/device/revoke";
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+const REFRESH_TOKEN_GRANT: &str = "refresh_token";
+
+/// State of the stand-in authorization server.
+#[derive(Clone, Debug)]
+pub(crate) struct OAuthState {
+    pub(crate) interval: Duration,
+    pub(crate) access_token_lifetime: Duration,
+    devices: Vec<DeviceGrant>,
+    /// Refresh tokens which haven't been used yet, they are rotated on every refresh unless
+    /// `rotate_refresh_tokens` is disabled.
+    refresh_tokens: HashSet<String>,
+    pub(crate) rotate_refresh_tokens: bool,
+    pub(crate) refresh_count: usize,
+    pub(crate) revoked_tokens: Vec<String>,
+    /// Errors returned by the token endpoint before anything else, e.g. `slow_down`.
+    queued_errors: VecDeque<String>,
+}
+
+#[derive(Clone, Debug)]
+struct DeviceGrant {
+    device_code: String,
+    user_code: String,
+    client_id: String,
+    status: DeviceStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DeviceStatus {
+    Pending,
+    Approved,
+    Denied,
+    Redeemed,
+}
+
+impl Default for OAuthState {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            access_token_lifetime: Duration::from_secs(3600),
+            devices: Vec::new(),
+            refresh_tokens: HashSet::new(),
+            rotate_refresh_tokens: true,
+            refresh_count: 0,
+            revoked_tokens: Vec::new(),
+            queued_errors: VecDeque::new(),
+        }
+    }
+}
+
+impl OAuthState {
+    fn set_status(&mut self, user_code: &str, status: DeviceStatus) -> bool {
+        self.devices
+            .iter_mut()
+            .find(|device| device.user_code == user_code)
+            .map(|device| device.status = status)
+            .is_some()
+    }
+
+    pub(crate) fn approve(&mut self, user_code: &str) -> bool {
+        self.set_status(user_code, DeviceStatus::Approved)
+    }
+
+    pub(crate) fn deny(&mut self, user_code: &str) -> bool {
+        self.set_status(user_code, DeviceStatus::Denied)
+    }
+
+    pub(crate) fn queue_error(&mut self, error: &str) {
+        self.queued_errors.push_back(error.to_string());
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthorizeForm {
+    client_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenForm {
+    grant_type: String,
+    client_id: String,
+    device_code: Option<String>,
+    refresh_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RevokeForm {
+    token: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+    token_type: &'static str,
+    expires_in: u64,
+    scope: &'static str,
+}
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route(AUTHORIZE_PATH, post(authorize))
+        .route(TOKEN_PATH, post(token))
+        .route(REVOKE_PATH, post(revoke))
+}
+
+fn oauth_error(error: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": error,
+            "error_description": format!("The request failed with '{error}'."),
+        })),
+    )
+        .into_response()
+}
+
+fn random_hex() -> String {
+    format!("{:032x}", rand::thread_rng().gen::<u128>())
+}
+
+/// Creates an unsigned JWT carrying the expiry, which is all clients need to inspect.
+fn access_token(lifetime: Duration) -> String {
+    let exp = (SystemTime::now() + lifetime)
+        .duration_since(UNIX_EPOCH)
+        .expect("time after epoch")
+        .as_secs();
+    let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"none","typ":"JWT"}"#);
+    let claims = URL_SAFE_NO_PAD.encode(json!({ "exp": exp, "jti": random_hex() }).to_string());
+    format!("{header}.{claims}.")
+}
+
+async fn authorize(State(state): State<AppState>, Form(form): Form<AuthorizeForm>) -> Response {
+    let mut oauth = state.oauth();
+    let code = random_hex();
+    let user_code = format!("{}-{}", &code[..4], &code[4..8]).to_uppercase();
+    let device_code = random_hex();
+
+    oauth.devices.push(DeviceGrant {
+        device_code: device_code.clone(),
+        user_code: user_code.clone(),
+        client_id: form.client_id,
+        status: DeviceStatus::Pending,
+    });
+
+    let verification_uri = state
+        .base_url
+        .join("account/connect")
+        .expect("valid verification URI")
+        .to_string();
+
+    Json(json!({
+        "device_code": device_code,
+        "user_code": user_code,
+        "verification_uri": verification_uri,
+        "expires_in": 600,
+        "interval": oauth.interval.as_secs(),
+    }))
+    .into_response()
+}
+
+async fn token(State(state): State<AppState>, Form(form): Form<TokenForm>) -> Response {
+    let mut oauth = state.oauth();
+    if let Some(error) = oauth.queued_errors.pop_front() {
+        return oauth_error(&error);
+    }
+
+    match form.grant_type.as_str() {
+        DEVICE_CODE_GRANT => {
+            let device_code = form.device_code.unwrap_or_default();
+            let Some(device) = oauth.devices.iter_mut().find(|device| {
+                device.device_code == device_code && device.client_id == form.client_id
+            }) else {
+                return oauth_error("invalid_grant");
+            };
+
+            match device.status {
+                DeviceStatus::Pending => return oauth_error("authorization_pending"),
+                DeviceStatus::Denied => return oauth_error("access_denied"),
+                DeviceStatus::Redeemed => return oauth_error("invalid_grant"),
+                DeviceStatus::Approved => device.status = DeviceStatus::Redeemed,
+            }
+        }
+        REFRESH_TOKEN_GRANT => {
+            let refresh_token = form.refresh_token.unwrap_or_default();
+            if !oauth.refresh_tokens.contains(&refresh_token) {
+                return oauth_error("invalid_grant");
+            }
+            oauth.refresh_count += 1;
+            if oauth.rotate_refresh_tokens {
+                oauth.refresh_tokens.remove(&refresh_token);
+            }
+        }
+        _ => return oauth_error("unsupported_grant_type"),
+    }
+
+    // Without rotation, only the device grant issues a refresh token.
+    let refresh_token =
+        (oauth.rotate_refresh_tokens || form.grant_type == DEVICE_CODE_GRANT).then(random_hex);
+    if let Some(refresh_token) = &refresh_token {
+        oauth.refresh_tokens.insert(refresh_token.clone());
+    }
+    let response = TokenResponse {
+        access_token: access_token(oauth.access_token_lifetime),
+        refresh_token,
+        token_type: "Bearer",
+        expires_in: oauth.access_token_lifetime.as_secs(),
+        scope: "openid profile offline_access",
+    };
+    drop(oauth);
+
+    state
+        .credentials()
+        .access_tokens
+        .insert(response.access_token.clone());
+
+    Json(response).into_response()
+}
+
+/// RFC 7009 token revocation, which succeeds for unknown tokens too.
+async fn revoke(State(state): State<AppState>, Form(form): Form<RevokeForm>) -> Response {
+    let mut oauth = state.oauth();
+    oauth.refresh_tokens.remove(&form.token);
+    oauth.revoked_tokens.push(form.token.clone());
+    drop(oauth);
+
+    state.credentials().access_tokens.remove(&form.token);
+
+    StatusCode::OK.into_response()
+}
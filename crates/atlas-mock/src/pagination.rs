@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::error::ApiError;
+
+const DEFAULT_ITEMS_PER_PAGE: usize = 100;
+const MAX_ITEMS_PER_PAGE: usize = 500;
+
+/// The `pageNum`, `itemsPerPage` and `includeCount` query parameters.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PageQuery {
+    page_num: Option<usize>,
+    items_per_page: Option<usize>,
+    include_count: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Page {
+    links: Vec<Link>,
+    results: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_count: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct Link {
+    href: String,
+    rel: &'static str,
+}
+
+impl PageQuery {
+    /// Slices `items` into the requested page, with `self`, `previous` and `next` links
+    /// relative to `url`.
+    pub(crate) fn paginate(&self, items: Vec<Value>, url: &Url) -> Result<Page, ApiError> {
+        let page_num = self.page_num.unwrap_or(1);
+        if page_num == 0 {
+            return Err(ApiError::invalid_attribute("pageNum"));
+        }
+
+        let items_per_page = self.items_per_page.unwrap_or(DEFAULT_ITEMS_PER_PAGE);
+        if items_per_page == 0 || items_per_page > MAX_ITEMS_PER_PAGE {
+            return Err(ApiError::invalid_attribute("itemsPerPage"));
+        }
+
+        // The end of the page, which also bounds its start, may not fit a `usize`
+        let end = page_num
+            .checked_mul(items_per_page)
+            .ok_or_else(|| ApiError::invalid_attribute("pageNum"))?;
+
+        let total_count = items.len();
+        let results: Vec<_> = items
+            .into_iter()
+            .skip(end - items_per_page)
+            .take(items_per_page)
+            .collect();
+
+        let mut links = vec![Link {
+            href: page_url(url, page_num, items_per_page),
+            rel: "self",
+        }];
+        if page_num > 1 {
+            links.push(Link {
+                href: page_url(url, page_num - 1, items_per_page),
+                rel: "previous",
+            });
+        }
+        if end < total_count {
+            links.push(Link {
+                href: page_url(url, page_num + 1, items_per_page),
+                rel: "next",
+            });
+        }
+
+        Ok(Page {
+            links,
+            results,
+            total_count: self.include_count.unwrap_or(true).then_some(total_count),
+        })
+    }
+}
+
+fn page_url(url: &Url, page_num: usize, items_per_page: usize) -> String {
+    let mut url = url.clone();
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != "pageNum" && key != "itemsPerPage")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair("pageNum", &page_num.to_string())
+        .append_pair("itemsPerPage", &items_per_page.to_string());
+    url.to_string()
+}
@@ -0,0 +1,236 @@
+use axum::{
+    extract::{OriginalUri, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Json, Router,
+};
+use serde_json::Value;
+use url::Url;
+
+use crate::{
+    error::ApiError,
+    extract::{ApiJson, ApiQuery},
+    pagination::PageQuery,
+    AppState,
+};
+
+type ApiResult = Result<Response, ApiError>;
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/api/atlas/v2/orgs", get(list_orgs))
+        .route("/api/atlas/v2/orgs/:org_id", get(get_org))
+        .route(
+            "/api/atlas/v2/groups",
+            get(list_projects).post(create_project),
+        )
+        .route(
+            "/api/atlas/v2/groups/:group_id",
+            get(get_project).delete(delete_project),
+        )
+        .route(
+            "/api/atlas/v2/groups/:group_id/clusters",
+            get(list_clusters).post(create_cluster),
+        )
+        .route(
+            "/api/atlas/v2/groups/:group_id/clusters/:cluster_name",
+            get(get_cluster)
+                .patch(update_cluster)
+                .delete(delete_cluster),
+        )
+        .route(
+            "/api/atlas/v2/groups/:group_id/databaseUsers",
+            get(list_database_users).post(create_database_user),
+        )
+        .route(
+            "/api/atlas/v2/groups/:group_id/databaseUsers/:database_name/:username",
+            get(get_database_user).delete(delete_database_user),
+        )
+        .route(
+            "/api/atlas/v2/groups/:group_id/accessList",
+            get(list_access_list).post(add_access_list_entries),
+        )
+        .route(
+            "/api/atlas/v2/groups/:group_id/accessList/:entry_value",
+            delete(delete_access_list_entry),
+        )
+        .fallback(not_found)
+}
+
+fn request_url(state: &AppState, uri: &OriginalUri) -> Url {
+    state
+        .base_url
+        .join(&uri.0.to_string())
+        .expect("valid request URL")
+}
+
+fn page(state: &AppState, uri: &OriginalUri, query: &PageQuery, items: &[Value]) -> ApiResult {
+    let page = query.paginate(items.to_vec(), &request_url(state, uri))?;
+    Ok(Json(page).into_response())
+}
+
+async fn not_found(uri: OriginalUri) -> ApiError {
+    ApiError::not_found(
+        "RESOURCE_NOT_FOUND",
+        format!("Cannot find resource {}.", uri.0.path()),
+    )
+}
+
+async fn list_orgs(
+    State(state): State<AppState>,
+    uri: OriginalUri,
+    ApiQuery(query): ApiQuery<PageQuery>,
+) -> ApiResult {
+    let mock = state.lock();
+    page(&state, &uri, &query, mock.orgs())
+}
+
+async fn get_org(State(state): State<AppState>, Path(org_id): Path<String>) -> ApiResult {
+    Ok(Json(state.lock().org(&org_id)?.clone()).into_response())
+}
+
+async fn list_projects(
+    State(state): State<AppState>,
+    uri: OriginalUri,
+    ApiQuery(query): ApiQuery<PageQuery>,
+) -> ApiResult {
+    let mock = state.lock();
+    page(&state, &uri, &query, mock.projects())
+}
+
+async fn create_project(State(state): State<AppState>, ApiJson(body): ApiJson<Value>) -> ApiResult {
+    Ok(Json(state.lock().create_project(body)?).into_response())
+}
+
+async fn get_project(State(state): State<AppState>, Path(group_id): Path<String>) -> ApiResult {
+    Ok(Json(state.lock().project(&group_id)?.clone()).into_response())
+}
+
+async fn delete_project(State(state): State<AppState>, Path(group_id): Path<String>) -> ApiResult {
+    state.lock().delete_project(&group_id)?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn list_clusters(
+    State(state): State<AppState>,
+    uri: OriginalUri,
+    Path(group_id): Path<String>,
+    ApiQuery(query): ApiQuery<PageQuery>,
+) -> ApiResult {
+    let mock = state.lock();
+    mock.project(&group_id)?;
+    page(&state, &uri, &query, mock.clusters(&group_id))
+}
+
+async fn create_cluster(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResult {
+    let cluster = state.lock().create_cluster(&group_id, body)?;
+    Ok((StatusCode::CREATED, Json(cluster)).into_response())
+}
+
+async fn get_cluster(
+    State(state): State<AppState>,
+    Path((group_id, cluster_name)): Path<(String, String)>,
+) -> ApiResult {
+    Ok(Json(state.lock().cluster(&group_id, &cluster_name)?.clone()).into_response())
+}
+
+async fn update_cluster(
+    State(state): State<AppState>,
+    Path((group_id, cluster_name)): Path<(String, String)>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResult {
+    let cluster = state
+        .lock()
+        .update_cluster(&group_id, &cluster_name, body)?;
+    Ok(Json(cluster).into_response())
+}
+
+async fn delete_cluster(
+    State(state): State<AppState>,
+    Path((group_id, cluster_name)): Path<(String, String)>,
+) -> ApiResult {
+    state.lock().delete_cluster(&group_id, &cluster_name)?;
+    Ok(StatusCode::ACCEPTED.into_response())
+}
+
+async fn list_database_users(
+    State(state): State<AppState>,
+    uri: OriginalUri,
+    Path(group_id): Path<String>,
+    ApiQuery(query): ApiQuery<PageQuery>,
+) -> ApiResult {
+    let mock = state.lock();
+    mock.project(&group_id)?;
+    page(&state, &uri, &query, mock.database_users(&group_id))
+}
+
+async fn create_database_user(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResult {
+    let user = state.lock().create_database_user(&group_id, body)?;
+    Ok((StatusCode::CREATED, Json(user)).into_response())
+}
+
+async fn get_database_user(
+    State(state): State<AppState>,
+    Path((group_id, database_name, username)): Path<(String, String, String)>,
+) -> ApiResult {
+    let mock = state.lock();
+    let user = mock.database_user(&group_id, &database_name, &username)?;
+    Ok(Json(user.clone()).into_response())
+}
+
+async fn delete_database_user(
+    State(state): State<AppState>,
+    Path((group_id, database_name, username)): Path<(String, String, String)>,
+) -> ApiResult {
+    state
+        .lock()
+        .delete_database_user(&group_id, &database_name, &username)?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn list_access_list(
+    State(state): State<AppState>,
+    uri: OriginalUri,
+    Path(group_id): Path<String>,
+    ApiQuery(query): ApiQuery<PageQuery>,
+) -> ApiResult {
+    let mock = state.lock();
+    mock.project(&group_id)?;
+    page(&state, &uri, &query, mock.access_list(&group_id))
+}
+
+/// Atlas answers with the complete, paginated access list after adding entries.
+async fn add_access_list_entries(
+    State(state): State<AppState>,
+    uri: OriginalUri,
+    Path(group_id): Path<String>,
+    ApiQuery(query): ApiQuery<PageQuery>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResult {
+    let mut mock = state.lock();
+    mock.add_access_list_entries(&group_id, body)?;
+    let page = query.paginate(
+        mock.access_list(&group_id).to_vec(),
+        &request_url(&state, &uri),
+    )?;
+    Ok((StatusCode::CREATED, Json(page)).into_response())
+}
+
+async fn delete_access_list_entry(
+    State(state): State<AppState>,
+    Path((group_id, entry_value)): Path<(String, String)>,
+) -> ApiResult {
+    state
+        .lock()
+        .delete_access_list_entry(&group_id, &entry_value)?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
@@ -0,0 +1,350 @@
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::error::ApiError;
+
+/// In-memory contents of the mock Atlas deployment.
+///
+/// Resources are stored as JSON documents in the shape the Admin API returns them.
+#[derive(Clone, Debug, Default)]
+pub struct MockState {
+    next_id: u64,
+    orgs: Vec<Value>,
+    projects: Vec<Value>,
+    clusters: BTreeMap<String, Vec<Value>>,
+    database_users: BTreeMap<String, Vec<Value>>,
+    access_list: BTreeMap<String, Vec<Value>>,
+}
+
+impl MockState {
+    /// Generates an id which looks like an Atlas `ObjectId`.
+    fn generate_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("{:024x}", self.next_id)
+    }
+
+    pub fn add_org(&mut self, name: &str) -> String {
+        let id = self.generate_id();
+        self.orgs.push(json!({
+            "id": id,
+            "name": name,
+            "isDeleted": false,
+        }));
+        id
+    }
+
+    pub fn add_project(&mut self, org_id: &str, name: &str) -> String {
+        let id = self.generate_id();
+        self.projects.push(json!({
+            "id": id,
+            "name": name,
+            "orgId": org_id,
+            "clusterCount": 0,
+        }));
+        id
+    }
+
+    pub fn orgs(&self) -> &[Value] {
+        &self.orgs
+    }
+
+    pub fn projects(&self) -> &[Value] {
+        &self.projects
+    }
+
+    pub fn clusters(&self, group_id: &str) -> &[Value] {
+        self.clusters.get(group_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn database_users(&self, group_id: &str) -> &[Value] {
+        self.database_users.get(group_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn access_list(&self, group_id: &str) -> &[Value] {
+        self.access_list.get(group_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn org(&self, org_id: &str) -> Result<&Value, ApiError> {
+        self.orgs
+            .iter()
+            .find(|org| org["id"] == org_id)
+            .ok_or_else(|| {
+                ApiError::not_found("ORG_NOT_FOUND", format!("Organization {org_id} not found."))
+                    .with_parameters([org_id.to_string()])
+            })
+    }
+
+    pub(crate) fn project(&self, group_id: &str) -> Result<&Value, ApiError> {
+        self.projects
+            .iter()
+            .find(|project| project["id"] == group_id)
+            .ok_or_else(|| {
+                ApiError::not_found(
+                    "GROUP_NOT_FOUND",
+                    format!("No group with ID {group_id} exists."),
+                )
+                .with_parameters([group_id.to_string()])
+            })
+    }
+
+    pub(crate) fn create_project(&mut self, body: Value) -> Result<Value, ApiError> {
+        let name = required_str(&body, "name")?.to_string();
+        let org_id = required_str(&body, "orgId")?.to_string();
+        self.org(&org_id)?;
+
+        if self.projects.iter().any(|project| project["name"] == name) {
+            return Err(ApiError::conflict(
+                "GROUP_ALREADY_EXISTS",
+                format!("A group with name \"{name}\" already exists."),
+            ));
+        }
+
+        let id = self.add_project(&org_id, &name);
+        self.project(&id).cloned()
+    }
+
+    pub(crate) fn delete_project(&mut self, group_id: &str) -> Result<(), ApiError> {
+        self.project(group_id)?;
+        if !self.clusters(group_id).is_empty() {
+            return Err(ApiError::conflict(
+                "CANNOT_CLOSE_GROUP_ACTIVE_ATLAS_CLUSTERS",
+                "Please terminate all clusters before deleting this project.",
+            ));
+        }
+
+        self.projects.retain(|project| project["id"] != group_id);
+        self.database_users.remove(group_id);
+        self.access_list.remove(group_id);
+        Ok(())
+    }
+
+    pub(crate) fn cluster(&self, group_id: &str, name: &str) -> Result<&Value, ApiError> {
+        self.project(group_id)?;
+        self.clusters(group_id)
+            .iter()
+            .find(|cluster| cluster["name"] == name)
+            .ok_or_else(|| {
+                ApiError::not_found(
+                    "CLUSTER_NOT_FOUND",
+                    format!("No cluster named {name} exists in group {group_id}."),
+                )
+                .with_parameters([name.to_string(), group_id.to_string()])
+            })
+    }
+
+    pub(crate) fn create_cluster(
+        &mut self,
+        group_id: &str,
+        body: Value,
+    ) -> Result<Value, ApiError> {
+        self.project(group_id)?;
+        let name = required_str(&body, "name")?.to_string();
+
+        if self.cluster(group_id, &name).is_ok() {
+            return Err(ApiError::conflict(
+                "DUPLICATE_CLUSTER_NAME",
+                format!("Cluster {name} already exists in group {group_id}."),
+            ));
+        }
+
+        let id = self.generate_id();
+        let mut cluster = into_object(body)?;
+        cluster.insert("id".to_string(), json!(id));
+        cluster.insert("groupId".to_string(), json!(group_id));
+        cluster.insert("stateName".to_string(), json!("IDLE"));
+        cluster
+            .entry("clusterType")
+            .or_insert_with(|| json!("REPLICASET"));
+        let cluster = Value::Object(cluster);
+
+        self.clusters
+            .entry(group_id.to_string())
+            .or_default()
+            .push(cluster.clone());
+        self.update_cluster_count(group_id);
+        Ok(cluster)
+    }
+
+    pub(crate) fn update_cluster(
+        &mut self,
+        group_id: &str,
+        name: &str,
+        body: Value,
+    ) -> Result<Value, ApiError> {
+        self.cluster(group_id, name)?;
+        let changes = into_object(body)?;
+
+        let cluster = self
+            .clusters
+            .get_mut(group_id)
+            .and_then(|clusters| clusters.iter_mut().find(|cluster| cluster["name"] == name))
+            .expect("cluster exists");
+
+        for (key, value) in changes {
+            if !matches!(key.as_str(), "id" | "groupId" | "name") {
+                cluster[key] = value;
+            }
+        }
+
+        Ok(cluster.clone())
+    }
+
+    pub(crate) fn delete_cluster(&mut self, group_id: &str, name: &str) -> Result<(), ApiError> {
+        self.cluster(group_id, name)?;
+        if let Some(clusters) = self.clusters.get_mut(group_id) {
+            clusters.retain(|cluster| cluster["name"] != name);
+        }
+        self.update_cluster_count(group_id);
+        Ok(())
+    }
+
+    fn update_cluster_count(&mut self, group_id: &str) {
+        let count = self.clusters(group_id).len();
+        if let Some(project) = self
+            .projects
+            .iter_mut()
+            .find(|project| project["id"] == group_id)
+        {
+            project["clusterCount"] = json!(count);
+        }
+    }
+
+    pub(crate) fn database_user(
+        &self,
+        group_id: &str,
+        database_name: &str,
+        username: &str,
+    ) -> Result<&Value, ApiError> {
+        self.project(group_id)?;
+        self.database_users(group_id)
+            .iter()
+            .find(|user| user["databaseName"] == database_name && user["username"] == username)
+            .ok_or_else(|| {
+                ApiError::not_found(
+                    "USERNAME_NOT_FOUND",
+                    format!("No user with username {username} exists."),
+                )
+                .with_parameters([username.to_string()])
+            })
+    }
+
+    pub(crate) fn create_database_user(
+        &mut self,
+        group_id: &str,
+        body: Value,
+    ) -> Result<Value, ApiError> {
+        self.project(group_id)?;
+        let mut user = into_object(body)?;
+        user.remove("password");
+        user.insert("groupId".to_string(), json!(group_id));
+        user.entry("databaseName").or_insert_with(|| json!("admin"));
+        let user = Value::Object(user);
+
+        let username = required_str(&user, "username")?;
+        let database_name = required_str(&user, "databaseName")?;
+        if self
+            .database_user(group_id, database_name, username)
+            .is_ok()
+        {
+            return Err(ApiError::conflict(
+                "USER_ALREADY_EXISTS",
+                format!("The specified user {username} already exists."),
+            ));
+        }
+
+        self.database_users
+            .entry(group_id.to_string())
+            .or_default()
+            .push(user.clone());
+        Ok(user)
+    }
+
+    pub(crate) fn delete_database_user(
+        &mut self,
+        group_id: &str,
+        database_name: &str,
+        username: &str,
+    ) -> Result<(), ApiError> {
+        self.database_user(group_id, database_name, username)?;
+        if let Some(users) = self.database_users.get_mut(group_id) {
+            users.retain(|user| {
+                user["databaseName"] != database_name || user["username"] != username
+            });
+        }
+        Ok(())
+    }
+
+    /// Adds access list entries, replacing existing entries with the same address.
+    pub(crate) fn add_access_list_entries(
+        &mut self,
+        group_id: &str,
+        body: Value,
+    ) -> Result<(), ApiError> {
+        self.project(group_id)?;
+        let Value::Array(entries) = body else {
+            return Err(ApiError::invalid_attribute("body"));
+        };
+
+        let mut added = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let mut entry = into_object(entry)?;
+            let cidr_block = match (entry.get("cidrBlock"), entry.get("ipAddress")) {
+                (Some(Value::String(cidr_block)), _) => cidr_block.clone(),
+                (None, Some(Value::String(ip_address))) => format!("{ip_address}/32"),
+                _ => return Err(ApiError::invalid_attribute("cidrBlock")),
+            };
+            entry.insert("cidrBlock".to_string(), json!(cidr_block));
+            entry.insert("groupId".to_string(), json!(group_id));
+            added.push(Value::Object(entry));
+        }
+
+        let access_list = self.access_list.entry(group_id.to_string()).or_default();
+        for entry in added {
+            access_list.retain(|existing| existing["cidrBlock"] != entry["cidrBlock"]);
+            access_list.push(entry);
+        }
+        Ok(())
+    }
+
+    /// Removes the entry matching `entry_value`, which is either an IP address or a CIDR block.
+    pub(crate) fn delete_access_list_entry(
+        &mut self,
+        group_id: &str,
+        entry_value: &str,
+    ) -> Result<(), ApiError> {
+        self.project(group_id)?;
+        let access_list = self.access_list.entry(group_id.to_string()).or_default();
+        let before = access_list.len();
+        access_list.retain(|entry| {
+            entry["cidrBlock"] != entry_value && entry.get("ipAddress") != Some(&json!(entry_value))
+        });
+
+        if access_list.len() == before {
+            return Err(ApiError::not_found(
+                "ATLAS_NETWORK_PERMISSION_ENTRY_NOT_FOUND",
+                format!("IP Address {entry_value} not on Atlas access list for group {group_id}."),
+            )
+            .with_parameters([entry_value.to_string(), group_id.to_string()]));
+        }
+        Ok(())
+    }
+}
+
+fn into_object(value: Value) -> Result<Map<String, Value>, ApiError> {
+    match value {
+        Value::Object(map) => Ok(map),
+        _ => Err(ApiError::invalid_attribute("body")),
+    }
+}
+
+fn required_str<'a>(value: &'a Value, attribute: &str) -> Result<&'a str, ApiError> {
+    value[attribute].as_str().ok_or_else(|| {
+        ApiError::new(
+            axum::http::StatusCode::BAD_REQUEST,
+            "MISSING_ATTRIBUTE",
+            format!("The required attribute {attribute} was not specified."),
+        )
+        .with_parameters([attribute.to_string()])
+    })
+}
@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+
+use atlas_core::client::{
+    md5_hex, split_params, Client, Credentials, ExecuteError, HttpRequest, HttpResponse, Method,
+};
+use atlas_core::{AtlasURL, TryFromMap};
+use atlas_mock::MockServer;
+use serde_json::{json, Value};
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters")]
+struct Clusters {
+    group_id: String,
+}
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters/{cluster_name}")]
+struct Cluster {
+    group_id: String,
+    cluster_name: String,
+}
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/databaseUsers")]
+struct DatabaseUsers {
+    group_id: String,
+}
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters")]
+#[url(method = "POST", version = "2023-02-01", response = Value)]
+struct CreateCluster {
+    group_id: String,
+    #[url(body)]
+    cluster: Value,
+}
+
+#[derive(AtlasURL)]
+#[url("/api/atlas/v2/groups/{group_id}/clusters/{cluster_name}")]
+#[url(method = "GET", version = "2023-02-01", response = Value)]
+struct GetCluster {
+    group_id: String,
+    cluster_name: String,
+}
+
+#[derive(TryFromMap, Debug, PartialEq)]
+#[map(rename_all = "camelCase")]
+struct Pagination {
+    page_num: u32,
+    items_per_page: u32,
+}
+
+fn body(response: &HttpResponse) -> Value {
+    serde_json::from_str(&response.body).unwrap()
+}
+
+fn client(server: &MockServer) -> Client {
+    Client::new(server.base_url().clone())
+}
+
+async fn get(client: &Client, path_and_query: &str) -> HttpResponse {
+    let url = client.base_url().join(path_and_query).unwrap();
+    client
+        .send_http(HttpRequest::new(Method::Get, url))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn digest_authentication() {
+    let server = MockServer::builder()
+        .api_key("public", "private")
+        .start()
+        .await
+        .unwrap();
+    let org_id = server.state().add_org("org");
+    let group_id = server.state().add_project(&org_id, "project");
+
+    let client = client(&server).with_credentials(Credentials::ApiKeys {
+        public: "public".to_string(),
+        private: "private".to_string(),
+    });
+    let response = client
+        .send(Method::Get, &Clusters { group_id }, None)
+        .await
+        .unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(body(&response)["totalCount"], 0);
+
+    let client = client.with_credentials(Credentials::ApiKeys {
+        public: "public".to_string(),
+        private: "wrong".to_string(),
+    });
+    let response = get(&client, "/api/atlas/v2/orgs").await;
+    assert_eq!(response.status, 401);
+    assert!(response
+        .header("www-authenticate")
+        .is_some_and(|challenge| challenge.starts_with("Digest ")));
+    assert_eq!(body(&response)["errorCode"], "NOT_ATLAS_USER");
+}
+
+#[tokio::test]
+async fn digest_authentication_checks_the_uri() {
+    let server = MockServer::builder()
+        .api_key("public", "private")
+        .start()
+        .await
+        .unwrap();
+    let client = client(&server);
+
+    let response = get(&client, "/api/atlas/v2/orgs").await;
+    let challenge = response.header("www-authenticate").unwrap();
+    let params: BTreeMap<_, _> = split_params(challenge.strip_prefix("Digest ").unwrap())
+        .into_iter()
+        .collect();
+    let nonce = &params["nonce"];
+
+    // A digest computed for `/api/atlas/v2/orgs`, replayed against another path
+    let ha1 = md5_hex("public:MMS Public API:private");
+    let ha2 = md5_hex("GET:/api/atlas/v2/orgs");
+    let response = md5_hex(&format!("{ha1}:{nonce}:{ha2}"));
+    let authorization = format!(
+        r#"Digest username="public", realm="MMS Public API", nonce="{nonce}", uri="/api/atlas/v2/orgs", response="{response}""#
+    );
+    for (path, status) in [("/api/atlas/v2/orgs", 200), ("/api/atlas/v2/groups", 401)] {
+        let mut request = HttpRequest::new(Method::Get, client.base_url().join(path).unwrap());
+        request
+            .headers
+            .push(("Authorization".to_string(), authorization.clone()));
+        assert_eq!(client.send_http(request).await.unwrap().status, status);
+    }
+}
+
+#[tokio::test]
+async fn malformed_requests() {
+    let server = MockServer::start().await.unwrap();
+    let org_id = server.state().add_org("org");
+    let group_id = server.state().add_project(&org_id, "project");
+    let client = client(&server);
+
+    let response = get(&client, "/api/atlas/v2/groups?pageNum=first").await;
+    assert_eq!(response.status, 400);
+    assert_eq!(body(&response)["error"], 400);
+    assert_eq!(body(&response)["errorCode"], "INVALID_QUERY_PARAMETER");
+    assert_eq!(body(&response)["reason"], "Bad Request");
+
+    let url = client
+        .base_url()
+        .join(&format!("/api/atlas/v2/groups/{group_id}/clusters"))
+        .unwrap();
+    let mut request = HttpRequest::new(Method::Post, url);
+    request
+        .headers
+        .push(("Content-Type".to_string(), "application/json".to_string()));
+    request.body = Some("{".to_string());
+    let response = client.send_http(request).await.unwrap();
+    assert_eq!(response.status, 400);
+    assert_eq!(body(&response)["errorCode"], "INVALID_JSON");
+    assert_eq!(body(&response)["parameters"], json!([]));
+}
+
+#[tokio::test]
+async fn bearer_authentication() {
+    let server = MockServer::builder()
+        .access_token("token")
+        .start()
+        .await
+        .unwrap();
+
+    let response = get(&client(&server), "/api/atlas/v2/orgs").await;
+    assert_eq!(response.status, 401);
+
+    let client = client(&server).with_credentials(Credentials::AccessToken("token".to_string()));
+    let response = get(&client, "/api/atlas/v2/orgs").await;
+    assert_eq!(response.status, 200);
+
+    server.remove_access_token("token");
+    let response = get(&client, "/api/atlas/v2/orgs").await;
+    assert_eq!(response.status, 401);
+}
+
+#[tokio::test]
+async fn clusters() {
+    let server = MockServer::start().await.unwrap();
+    let org_id = server.state().add_org("org");
+    let group_id = server.state().add_project(&org_id, "project");
+    let client = client(&server);
+
+    let clusters = Clusters {
+        group_id: group_id.clone(),
+    };
+    let create = || {
+        client.send(
+            Method::Post,
+            &clusters,
+            Some(json!({ "name": "Cluster0" }).to_string()),
+        )
+    };
+    let response = create().await.unwrap();
+    assert_eq!(response.status, 201);
+    assert_eq!(body(&response)["stateName"], "IDLE");
+    assert_eq!(server.state().projects()[0]["clusterCount"], 1);
+
+    let response = create().await.unwrap();
+    assert_eq!(response.status, 409);
+    assert_eq!(body(&response)["errorCode"], "DUPLICATE_CLUSTER_NAME");
+
+    let cluster = Cluster {
+        group_id: group_id.clone(),
+        cluster_name: "Cluster0".to_string(),
+    };
+    let response = client
+        .send(
+            Method::Patch,
+            &cluster,
+            Some(json!({ "paused": true }).to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(body(&response)["paused"], true);
+
+    let response = client.send(Method::Delete, &cluster, None).await.unwrap();
+    assert_eq!(response.status, 202);
+
+    let response = client.send(Method::Get, &cluster, None).await.unwrap();
+    assert_eq!(response.status, 404);
+    assert_eq!(
+        body(&response),
+        json!({
+            "detail": format!("No cluster named Cluster0 exists in group {group_id}."),
+            "error": 404,
+            "errorCode": "CLUSTER_NOT_FOUND",
+            "parameters": ["Cluster0", group_id],
+            "reason": "Not Found",
+        })
+    );
+}
+
+#[tokio::test]
+async fn execute_requests() {
+    let server = MockServer::start().await.unwrap();
+    let org_id = server.state().add_org("org");
+    let group_id = server.state().add_project(&org_id, "project");
+    let client = client(&server);
+
+    let cluster = client
+        .execute(&CreateCluster {
+            group_id: group_id.clone(),
+            cluster: json!({ "name": "Cluster0" }),
+        })
+        .await
+        .unwrap();
+    assert_eq!(cluster["name"], "Cluster0");
+
+    let get = |cluster_name: &str| GetCluster {
+        group_id: group_id.clone(),
+        cluster_name: cluster_name.to_string(),
+    };
+    let cluster = client.execute(&get("Cluster0")).await.unwrap();
+    assert_eq!(cluster["stateName"], "IDLE");
+
+    let error = client.execute(&get("Cluster1")).await.unwrap_err();
+    assert!(matches!(
+        error,
+        ExecuteError::Status { status: 404, body } if body.contains("CLUSTER_NOT_FOUND")
+    ));
+}
+
+#[tokio::test]
+async fn pagination() {
+    let server = MockServer::start().await.unwrap();
+    let org_id = server.state().add_org("org");
+    for i in 0..5 {
+        server.state().add_project(&org_id, &format!("project-{i}"));
+    }
+    let client = client(&server);
+
+    let response = get(&client, "/api/atlas/v2/groups?itemsPerPage=2&pageNum=2").await;
+    let page = body(&response);
+    assert_eq!(page["totalCount"], 5);
+    assert_eq!(page["results"][0]["name"], "project-2");
+    assert_eq!(page["results"][1]["name"], "project-3");
+
+    let rels: Vec<_> = page["links"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|link| link["rel"].as_str().unwrap())
+        .collect();
+    assert_eq!(rels, ["self", "previous", "next"]);
+
+    let next = page["links"][2]["href"].as_str().unwrap();
+    let next_url = client.base_url().join(next).unwrap();
+    assert_eq!(
+        Pagination::try_from_url(&next_url).unwrap(),
+        Pagination {
+            page_num: 3,
+            items_per_page: 2,
+        }
+    );
+    let response = get(&client, next).await;
+    let page = body(&response);
+    assert_eq!(page["results"].as_array().unwrap().len(), 1);
+    assert_eq!(page["links"].as_array().unwrap().len(), 2);
+
+    let response = get(&client, "/api/atlas/v2/groups?includeCount=false").await;
+    assert_eq!(body(&response).get("totalCount"), None);
+
+    let response = get(&client, "/api/atlas/v2/groups?itemsPerPage=501").await;
+    assert_eq!(response.status, 400);
+    assert_eq!(body(&response)["errorCode"], "INVALID_ATTRIBUTE");
+
+    let response = get(
+        &client,
+        "/api/atlas/v2/groups?pageNum=18446744073709551615&itemsPerPage=2",
+    )
+    .await;
+    assert_eq!(response.status, 400);
+    assert_eq!(body(&response)["parameters"], json!(["pageNum"]));
+}
+
+#[tokio::test]
+async fn database_users_and_access_list() {
+    let server = MockServer::start().await.unwrap();
+    let org_id = server.state().add_org("org");
+    let group_id = server.state().add_project(&org_id, "project");
+    let client = client(&server);
+
+    let response = client
+        .send(
+            Method::Post,
+            &DatabaseUsers {
+                group_id: group_id.clone(),
+            },
+            Some(json!({ "username": "app", "password": "secret", "roles": [] }).to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status, 201);
+    assert_eq!(body(&response)["databaseName"], "admin");
+    assert_eq!(body(&response).get("password"), None);
+
+    let response = get(
+        &client,
+        &format!("/api/atlas/v2/groups/{group_id}/databaseUsers/admin/app"),
+    )
+    .await;
+    assert_eq!(response.status, 200);
+
+    let url = client
+        .base_url()
+        .join(&format!("/api/atlas/v2/groups/{group_id}/accessList"))
+        .unwrap();
+    let mut request = HttpRequest::new(Method::Post, url);
+    request
+        .headers
+        .push(("Content-Type".to_string(), "application/json".to_string()));
+    request.body =
+        Some(json!([{ "ipAddress": "10.0.0.1" }, { "cidrBlock": "10.1.0.0/16" }]).to_string());
+    let response = client.send_http(request).await.unwrap();
+    assert_eq!(body(&response)["totalCount"], 2);
+
+    let url = client
+        .base_url()
+        .join(&format!(
+            "/api/atlas/v2/groups/{group_id}/accessList/10.1.0.0%2F16"
+        ))
+        .unwrap();
+    let response = client
+        .send_http(HttpRequest::new(Method::Delete, url))
+        .await
+        .unwrap();
+    assert_eq!(response.status, 204);
+    assert_eq!(server.state().access_list(&group_id).len(), 1);
+
+    let response = get(&client, "/api/atlas/v2/groups/unknown/clusters").await;
+    assert_eq!(body(&response)["errorCode"], "GROUP_NOT_FOUND");
+}